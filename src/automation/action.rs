@@ -0,0 +1,40 @@
+use super::Condition;
+use crate::services::ServiceCall;
+use crate::types::Target;
+use crate::HassResult;
+
+use serde_json::Value;
+use std::time::Duration;
+
+/// A step of a run, the actions of an automation run one after the other
+///
+/// [Actions](https://www.home-assistant.io/docs/scripts/)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    CallService {
+        domain: String,
+        service: String,
+        data: Option<Value>,
+        target: Option<Target>,
+    },
+    FireEvent {
+        event_type: String,
+        data: Option<Value>,
+    },
+    Delay(Duration),
+    /// Stops the run if the condition does not hold
+    Condition(Condition),
+}
+
+impl Action {
+    /// An action calling one of the typed services of the `services` module
+    #[allow(clippy::result_large_err)]
+    pub fn service<S: ServiceCall>(call: &S) -> HassResult<Self> {
+        Ok(Self::CallService {
+            domain: call.domain().to_owned(),
+            service: call.service().to_owned(),
+            data: call.service_data()?,
+            target: call.target().filter(|target| !target.is_empty()).cloned(),
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use crate::types::{EntityId, HassEntity};
+
+use chrono::{DateTime, Datelike, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+/// What has to hold for a run to go on
+///
+/// [Conditions](https://www.home-assistant.io/docs/scripts/conditions/)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// The entity is in one of the states
+    State {
+        entity_id: EntityId,
+        state: Vec<String>,
+    },
+    /// The state, or the attribute, of the entity is a number within the bounds, both exclusive
+    NumericState {
+        entity_id: EntityId,
+        attribute: Option<String>,
+        above: Option<f64>,
+        below: Option<f64>,
+    },
+    /// The time of the server is within the window, `after` included and `before` excluded,
+    /// on one of the `weekdays` if any
+    ///
+    /// The window spans midnight when `after` is later than `before`.
+    Time {
+        after: Option<NaiveTime>,
+        before: Option<NaiveTime>,
+        weekdays: Vec<Weekday>,
+    },
+    /// The template renders to `true`, `yes`, `on`, `enable` or a number other than zero
+    Template(String),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    /// None of the conditions holds
+    Not(Vec<Condition>),
+}
+
+/// What the conditions are checked against
+///
+/// The engine renders the templates of the conditions beforehand, with
+/// `HassClient::render_template`.
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot<'a> {
+    pub states: &'a HashMap<EntityId, HassEntity>,
+    /// the current time in the time zone of the server
+    pub now: DateTime<Tz>,
+    /// the templates and their rendering
+    pub templates: &'a HashMap<String, String>,
+}
+
+impl Condition {
+    pub fn check(&self, snapshot: &Snapshot<'_>) -> bool {
+        match self {
+            Self::State { entity_id, state } => snapshot
+                .states
+                .get(entity_id)
+                .is_some_and(|entity| state.contains(&entity.state)),
+            Self::NumericState {
+                entity_id,
+                attribute,
+                above,
+                below,
+            } => {
+                let Some(entity) = snapshot.states.get(entity_id) else {
+                    return false;
+                };
+                let value = match attribute {
+                    Some(attribute) => match &entity.attributes[attribute] {
+                        serde_json::Value::String(value) => value.parse().ok(),
+                        value => value.as_f64(),
+                    },
+                    None => entity.state.parse::<f64>().ok(),
+                };
+                value.is_some_and(|value| {
+                    above.is_none_or(|above| value > above)
+                        && below.is_none_or(|below| value < below)
+                })
+            }
+            Self::Time {
+                after,
+                before,
+                weekdays,
+            } => {
+                let now = snapshot.now;
+                if !weekdays.is_empty() && !weekdays.contains(&now.weekday()) {
+                    return false;
+                }
+                let time = now.time();
+                match (after, before) {
+                    (Some(after), Some(before)) if after > before => {
+                        time >= *after || time < *before
+                    }
+                    _ => {
+                        after.is_none_or(|after| time >= after)
+                            && before.is_none_or(|before| time < before)
+                    }
+                }
+            }
+            Self::Template(template) => snapshot
+                .templates
+                .get(template)
+                .is_some_and(|rendered| is_truthy(rendered)),
+            Self::And(conditions) => conditions.iter().all(|condition| condition.check(snapshot)),
+            Self::Or(conditions) => conditions.iter().any(|condition| condition.check(snapshot)),
+            Self::Not(conditions) => !conditions.iter().any(|condition| condition.check(snapshot)),
+        }
+    }
+
+    /// collects the templates to render before checking the condition
+    pub(crate) fn templates<'a>(&'a self, templates: &mut Vec<&'a str>) {
+        match self {
+            Self::Template(template) => templates.push(template),
+            Self::And(conditions) | Self::Or(conditions) | Self::Not(conditions) => {
+                for condition in conditions {
+                    condition.templates(templates);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// the truthiness of a rendered template, the same as Home Assistant
+fn is_truthy(rendered: &str) -> bool {
+    let rendered = rendered.trim().to_lowercase();
+    match rendered.parse::<f64>() {
+        Ok(number) => number != 0.0,
+        Err(_) => matches!(rendered.as_str(), "true" | "yes" | "on" | "enable"),
+    }
+}
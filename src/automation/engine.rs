@@ -0,0 +1,513 @@
+use super::{
+    now, Action, Automation, Condition, Mode, RunOutcome, Snapshot, StepResult, Trace, Traces,
+    Trigger, TriggerData,
+};
+use crate::scheduler::{sleep_until, Location, Schedule};
+use crate::types::{EntityId, HassEntity, HassEvent};
+use crate::{runtime, HassClient, HassError, HassResult};
+
+use async_channel::{unbounded, Receiver, Sender};
+use futures_util::future::{AbortHandle, Abortable};
+use futures_util::lock::Mutex as AsyncMutex;
+use parking_lot::Mutex;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Runs automations against a client, see the module documentation
+pub struct AutomationEngine {
+    client: HassClient,
+    automations: Vec<Arc<Automation>>,
+    traces: Traces,
+}
+
+/// what the engine loop reacts to
+#[allow(clippy::large_enum_variant)]
+enum Signal {
+    Event(HassEvent),
+    /// a timer fired a trigger, `generation` identifies the delay of a `State` trigger
+    Fire {
+        automation: usize,
+        trigger: TriggerData,
+        generation: Option<u64>,
+    },
+    Done {
+        automation: usize,
+        run: u64,
+    },
+    /// a subscription ended, the connection is gone
+    Closed,
+}
+
+/// what the runs share
+struct Shared {
+    client: AsyncMutex<HassClient>,
+    states: Mutex<HashMap<EntityId, HassEntity>>,
+    location: Location,
+    traces: Traces,
+}
+
+#[derive(Default)]
+struct Runs {
+    running: HashMap<u64, (AbortHandle, Arc<Mutex<Trace>>)>,
+    queue: VecDeque<TriggerData>,
+}
+
+impl AutomationEngine {
+    pub fn new(client: HassClient) -> Self {
+        Self {
+            client,
+            automations: Vec::new(),
+            traces: Traces::default(),
+        }
+    }
+
+    pub fn add(&mut self, automation: Automation) {
+        self.automations.push(Arc::new(automation));
+    }
+
+    /// The traces of the last runs, updated while the engine runs
+    pub fn traces(&self) -> Traces {
+        self.traces.clone()
+    }
+
+    /// Runs the automations until the connection is closed
+    ///
+    /// The time zone and the states are fetched first, then the events of the triggers are
+    /// subscribed.
+    pub async fn run(self) -> HassResult<()> {
+        let Self {
+            mut client,
+            automations,
+            traces,
+        } = self;
+
+        let location = Location::from_config(&client.get_config().await?)
+            .ok_or_else(|| HassError::Generic("unknown time zone of the server".to_owned()))?;
+        let states = client
+            .get_states()
+            .await?
+            .into_iter()
+            .map(|entity| (entity.entity_id.clone(), entity))
+            .collect();
+
+        let (tx, rx) = unbounded();
+
+        let mut event_types = BTreeSet::from(["state_changed".to_owned()]);
+        for automation in &automations {
+            for trigger in &automation.triggers {
+                if let Trigger::Event { event_type, .. } = trigger {
+                    event_types.insert(event_type.clone());
+                }
+            }
+        }
+        for event_type in event_types {
+            let mut events = client.subscribe_event(&event_type).await?;
+            let tx = tx.clone();
+            runtime::spawn(async move {
+                while let Some(event) = events.recv().await {
+                    if tx.send(Signal::Event(event.event)).await.is_err() {
+                        return;
+                    }
+                }
+                tx.send(Signal::Closed).await.ok();
+            });
+        }
+
+        let shared = Arc::new(Shared {
+            client: AsyncMutex::new(client),
+            states: Mutex::new(states),
+            location,
+            traces,
+        });
+        for (index, automation) in automations.iter().enumerate() {
+            for (trigger_index, trigger) in automation.triggers.iter().enumerate() {
+                spawn_timer(&shared, &tx, index, trigger_index, trigger);
+            }
+        }
+
+        Engine {
+            shared,
+            automations,
+            tx,
+            runs: HashMap::new(),
+            delays: HashMap::new(),
+            next_run: 0,
+        }
+        .run(rx)
+        .await;
+        Ok(())
+    }
+}
+
+/// the state of the engine loop
+struct Engine {
+    shared: Arc<Shared>,
+    automations: Vec<Arc<Automation>>,
+    tx: Sender<Signal>,
+    runs: HashMap<usize, Runs>,
+    // the delayed `State` triggers, by automation and trigger
+    delays: HashMap<(usize, usize), u64>,
+    next_run: u64,
+}
+
+impl Engine {
+    async fn run(mut self, rx: Receiver<Signal>) {
+        while let Ok(signal) = rx.recv().await {
+            match signal {
+                Signal::Event(event) => self.event(event),
+                Signal::Fire {
+                    automation,
+                    trigger,
+                    generation: Some(generation),
+                } => {
+                    let key = (automation, trigger.index);
+                    if self.delays.get(&key) == Some(&generation) {
+                        self.delays.remove(&key);
+                        self.trigger(automation, trigger);
+                    }
+                }
+                Signal::Fire {
+                    automation,
+                    trigger,
+                    generation: None,
+                } => self.trigger(automation, trigger),
+                Signal::Done { automation, run } => self.done(automation, run),
+                Signal::Closed => break,
+            }
+        }
+
+        for runs in self.runs.into_values() {
+            for (handle, trace) in runs.running.into_values() {
+                handle.abort();
+                let mut trace = trace.lock().clone();
+                trace.finish(RunOutcome::Aborted);
+                self.shared.traces.push(trace);
+            }
+        }
+    }
+
+    fn event(&mut self, event: HassEvent) {
+        if event.event_type == "state_changed" {
+            self.state_changed(&event);
+        }
+
+        let automations = self.automations.clone();
+        for (index, automation) in automations.iter().enumerate() {
+            for (trigger_index, trigger) in automation.triggers.iter().enumerate() {
+                if trigger.matches_event(&event) {
+                    let mut data = TriggerData::new(trigger_index);
+                    data.event = Some(event.clone());
+                    self.trigger(index, data);
+                }
+            }
+        }
+    }
+
+    fn state_changed(&mut self, event: &HassEvent) {
+        let old = event.data.old_state.as_ref();
+        let new = event.data.new_state.as_ref();
+        let Some(entity_id) = event.data.entity_id.clone() else {
+            return;
+        };
+        {
+            let mut states = self.shared.states.lock();
+            match new {
+                Some(new) => states.insert(entity_id.clone(), new.clone()),
+                None => states.remove(&entity_id),
+            };
+        }
+
+        let automations = self.automations.clone();
+        for (index, automation) in automations.iter().enumerate() {
+            for (trigger_index, trigger) in automation.triggers.iter().enumerate() {
+                let Trigger::State {
+                    entity_id: watched,
+                    to,
+                    for_,
+                    ..
+                } = trigger
+                else {
+                    continue;
+                };
+                if *watched != entity_id {
+                    continue;
+                }
+
+                let key = (index, trigger_index);
+                if trigger.matches_state(old, new) {
+                    let mut data = TriggerData::new(trigger_index);
+                    data.entity_id = Some(entity_id.clone());
+                    data.from_state = old.cloned();
+                    data.to_state = new.cloned();
+
+                    match for_ {
+                        Some(delay) => {
+                            let generation = self.next_run;
+                            self.next_run += 1;
+                            self.delays.insert(key, generation);
+
+                            let tx = self.tx.clone();
+                            let delay = *delay;
+                            runtime::spawn(async move {
+                                runtime::sleep(delay).await;
+                                data.fired_at = now();
+                                let signal = Signal::Fire {
+                                    automation: index,
+                                    trigger: data,
+                                    generation: Some(generation),
+                                };
+                                tx.send(signal).await.ok();
+                            });
+                        }
+                        None => self.trigger(index, data),
+                    }
+                } else if self.delays.contains_key(&key) {
+                    // the entity left the state before the delay elapsed
+                    let new_state = new.map(|new| new.state.as_str());
+                    let left = match to {
+                        Some(to) => new_state != Some(to.as_str()),
+                        None => old.map(|old| old.state.as_str()) != new_state,
+                    };
+                    if left {
+                        self.delays.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// starts a run, or queues or skips it according to the mode of the automation
+    fn trigger(&mut self, index: usize, data: TriggerData) {
+        let automation = self.automations[index].clone();
+        let runs = self.runs.entry(index).or_default();
+
+        let start = match automation.mode {
+            Mode::Single => runs.running.is_empty(),
+            Mode::Restart => {
+                for (handle, trace) in runs.running.drain().map(|(_, run)| run) {
+                    handle.abort();
+                    let mut trace = trace.lock().clone();
+                    trace.finish(RunOutcome::Aborted);
+                    self.shared.traces.push(trace);
+                }
+                true
+            }
+            Mode::Queued { max } => {
+                if runs.running.is_empty() {
+                    true
+                } else {
+                    if runs.queue.len() + runs.running.len() < max {
+                        runs.queue.push_back(data);
+                        return;
+                    }
+                    false
+                }
+            }
+            Mode::Parallel { max } => runs.running.len() < max,
+        };
+
+        if start {
+            self.start(index, data);
+        } else {
+            log::debug!("{}: already running, skipped", automation.name);
+            let mut trace = Trace::new(&automation.name, data);
+            trace.finish(RunOutcome::Skipped);
+            self.shared.traces.push(trace);
+        }
+    }
+
+    fn start(&mut self, index: usize, data: TriggerData) {
+        let automation = self.automations[index].clone();
+        let run = self.next_run;
+        self.next_run += 1;
+
+        let trace = Arc::new(Mutex::new(Trace::new(&automation.name, data)));
+        let (handle, registration) = AbortHandle::new_pair();
+        self.runs
+            .entry(index)
+            .or_default()
+            .running
+            .insert(run, (handle, trace.clone()));
+
+        let shared = self.shared.clone();
+        let tx = self.tx.clone();
+        runtime::spawn(async move {
+            let steps = run_automation(&shared, &automation, &trace);
+            if Abortable::new(steps, registration).await.is_ok() {
+                shared.traces.push(trace.lock().clone());
+                tx.send(Signal::Done {
+                    automation: index,
+                    run,
+                })
+                .await
+                .ok();
+            }
+        });
+    }
+
+    fn done(&mut self, index: usize, run: u64) {
+        let Some(runs) = self.runs.get_mut(&index) else {
+            return;
+        };
+        runs.running.remove(&run);
+        if runs.running.is_empty() {
+            if let Some(data) = runs.queue.pop_front() {
+                self.start(index, data);
+            }
+        }
+    }
+}
+
+/// checks the conditions and runs the actions, recording them in the trace
+async fn run_automation(shared: &Shared, automation: &Automation, trace: &Mutex<Trace>) {
+    for (index, condition) in automation.conditions.iter().enumerate() {
+        let result = check(shared, condition).await;
+        let passed = matches!(result, StepResult::Passed);
+        let outcome = match &result {
+            StepResult::Error(err) => RunOutcome::Error(err.clone()),
+            _ => RunOutcome::ConditionFailed,
+        };
+
+        let mut trace = trace.lock();
+        trace.step(format!("condition/{index}"), result);
+        if !passed {
+            trace.finish(outcome);
+            return;
+        }
+    }
+
+    for (index, action) in automation.actions.iter().enumerate() {
+        let path = format!("action/{index}");
+        let result = match action {
+            Action::CallService {
+                domain,
+                service,
+                data,
+                target,
+            } => {
+                let mut client = shared.client.lock().await;
+                client
+                    .call_service_with_target(
+                        domain.clone(),
+                        service.clone(),
+                        data.clone(),
+                        target.clone(),
+                    )
+                    .await
+            }
+            Action::FireEvent { event_type, data } => {
+                let mut client = shared.client.lock().await;
+                client.fire_event(event_type, data.clone()).await
+            }
+            Action::Delay(delay) => {
+                runtime::sleep(*delay).await;
+                Ok(())
+            }
+            Action::Condition(condition) => {
+                let result = check(shared, condition).await;
+                let stop = match &result {
+                    StepResult::Passed => None,
+                    StepResult::Error(err) => Some(RunOutcome::Error(err.clone())),
+                    _ => Some(RunOutcome::Finished),
+                };
+
+                let mut trace = trace.lock();
+                trace.step(path, result);
+                if let Some(outcome) = stop {
+                    trace.finish(outcome);
+                    return;
+                }
+                continue;
+            }
+        };
+
+        let mut trace = trace.lock();
+        match result {
+            Ok(()) => trace.step(path, StepResult::Done),
+            Err(err) => {
+                log::warn!("{}: {path} failed: {err}", automation.name);
+                trace.step(path, StepResult::Error(err.to_string()));
+                trace.finish(RunOutcome::Error(err.to_string()));
+                return;
+            }
+        }
+    }
+
+    trace.lock().finish(RunOutcome::Finished);
+}
+
+/// renders the templates of the condition, then checks it against the current states
+async fn check(shared: &Shared, condition: &Condition) -> StepResult {
+    let mut templates = Vec::new();
+    condition.templates(&mut templates);
+
+    let mut rendered = HashMap::new();
+    for template in templates {
+        let mut client = shared.client.lock().await;
+        match client.render_template(template).await {
+            Ok(result) => rendered.insert(template.to_owned(), result),
+            Err(err) => return StepResult::Error(err.to_string()),
+        };
+    }
+
+    let states = shared.states.lock();
+    let snapshot = Snapshot {
+        states: &states,
+        now: now().with_timezone(&shared.location.tz),
+        templates: &rendered,
+    };
+    match condition.check(&snapshot) {
+        true => StepResult::Passed,
+        false => StepResult::Failed,
+    }
+}
+
+/// spawns the task firing a `Time`, `Interval`, `Sun` or `Cron` trigger
+fn spawn_timer(
+    shared: &Arc<Shared>,
+    tx: &Sender<Signal>,
+    automation: usize,
+    index: usize,
+    trigger: &Trigger,
+) {
+    let fire = {
+        let tx = tx.clone();
+        move || {
+            tx.try_send(Signal::Fire {
+                automation,
+                trigger: TriggerData::new(index),
+                generation: None,
+            })
+            .is_ok()
+        }
+    };
+
+    let schedule = match trigger.clone() {
+        Trigger::Time { at } => Schedule::Daily(at),
+        Trigger::Sun { event, offset } => Schedule::Sun { event, offset },
+        Trigger::Cron(cron) => Schedule::Cron(cron),
+        Trigger::Interval { every } => {
+            runtime::spawn(async move {
+                loop {
+                    runtime::sleep(every).await;
+                    if !fire() {
+                        return;
+                    }
+                }
+            });
+            return;
+        }
+        Trigger::State { .. } | Trigger::Event { .. } => return,
+    };
+    let location = shared.location;
+    runtime::spawn(async move {
+        let mut after = now();
+        while let Some(next) = schedule.next_after(&location, after) {
+            sleep_until(next).await;
+            if !fire() {
+                return;
+            }
+            after = next;
+        }
+    });
+}
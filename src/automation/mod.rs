@@ -0,0 +1,96 @@
+//! Automations written in Rust
+//!
+//! An `Automation` is made of the same parts as a Home Assistant automation: triggers starting
+//! its runs, conditions checked at the start of a run and actions run one after the other.
+//! `AutomationEngine` runs automations against a `HassClient`, according to their `Mode`, and
+//! keeps the `Trace`s of their last runs. The triggers and conditions can be checked without a
+//! server, with `Trigger::matches_state`, `Trigger::matches_event` and `Condition::check`.
+//!
+//! ```no_run
+//! # async fn run() -> hass_rs::HassResult<()> {
+//! use hass_rs::automation::{Action, Automation, AutomationEngine, Condition, Trigger};
+//! use hass_rs::services::light;
+//! use hass_rs::{EntityId, HassClient, Target};
+//! use std::time::Duration;
+//!
+//! let mut client = HassClient::new("ws://localhost:8123/api/websocket").await?;
+//! client.auth_with_longlivedtoken("token").await?;
+//!
+//! let mut hall = Automation::new("hall light on motion");
+//! hall.triggers.push(Trigger::State {
+//!     entity_id: EntityId::new("binary_sensor.hall_motion")?,
+//!     from: None,
+//!     to: Some("on".to_owned()),
+//!     for_: None,
+//! });
+//! hall.conditions.push(Condition::NumericState {
+//!     entity_id: EntityId::new("sensor.hall_lux")?,
+//!     attribute: None,
+//!     above: None,
+//!     below: Some(30.0),
+//! });
+//! hall.actions.push(Action::service(&light::TurnOn::new(Target::entity("light.hall")))?);
+//! hall.actions.push(Action::Delay(Duration::from_secs(120)));
+//! hall.actions.push(Action::service(&light::TurnOff::new(Target::entity("light.hall")))?);
+//!
+//! let mut engine = AutomationEngine::new(client);
+//! engine.add(hall);
+//! engine.run().await?;
+//! # Ok(())
+//! # }
+//! ```
+//! [Automations](https://www.home-assistant.io/docs/automation/)
+
+mod action;
+mod condition;
+mod engine;
+mod trace;
+mod trigger;
+
+pub use action::*;
+pub use condition::*;
+pub use engine::*;
+pub use trace::*;
+pub use trigger::*;
+
+use crate::scheduler::now;
+
+/// A rule run by `AutomationEngine`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Automation {
+    /// identifies the automation in the traces and the logs
+    pub name: String,
+    pub mode: Mode,
+    pub triggers: Vec<Trigger>,
+    /// all of them have to hold for the actions to run
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<Action>,
+}
+
+impl Automation {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            mode: Mode::default(),
+            triggers: Vec::new(),
+            conditions: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+}
+
+/// What happens when an automation is triggered while it is running
+///
+/// [Automation modes](https://www.home-assistant.io/docs/automation/modes/)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// The new run is skipped
+    #[default]
+    Single,
+    /// The running run is aborted and the new one started
+    Restart,
+    /// The new run starts once the previous ones are done, at most `max` runs running or waiting
+    Queued { max: usize },
+    /// The new run starts right away, at most `max` runs at the same time
+    Parallel { max: usize },
+}
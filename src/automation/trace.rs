@@ -0,0 +1,105 @@
+use super::TriggerData;
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// the number of traces kept per automation, the same as Home Assistant
+const TRACES_KEPT: usize = 5;
+
+/// The record of a run of an automation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub automation: String,
+    pub trigger: TriggerData,
+    pub started: DateTime<Utc>,
+    pub finished: Option<DateTime<Utc>>,
+    pub steps: Vec<TraceStep>,
+    pub outcome: RunOutcome,
+}
+
+/// A checked condition or a run action
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    /// e.g. `condition/0` or `action/2`
+    pub path: String,
+    pub result: StepResult,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepResult {
+    /// The condition holds
+    Passed,
+    /// The condition does not hold
+    Failed,
+    /// The action was run
+    Done,
+    Error(String),
+}
+
+/// How a run ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunOutcome {
+    Running,
+    /// All the actions were run, or an action condition stopped the run
+    Finished,
+    /// A condition of the automation does not hold, no action was run
+    ConditionFailed,
+    /// Not started, according to the mode of the automation
+    Skipped,
+    /// Stopped by a new run of a `Mode::Restart` automation, or by the end of the engine
+    Aborted,
+    /// An action failed
+    Error(String),
+}
+
+impl Trace {
+    pub(crate) fn new(automation: &str, trigger: TriggerData) -> Self {
+        Self {
+            automation: automation.to_owned(),
+            trigger,
+            started: super::now(),
+            finished: None,
+            steps: Vec::new(),
+            outcome: RunOutcome::Running,
+        }
+    }
+
+    pub(crate) fn step(&mut self, path: String, result: StepResult) {
+        self.steps.push(TraceStep { path, result });
+    }
+
+    pub(crate) fn finish(&mut self, outcome: RunOutcome) {
+        self.finished = Some(super::now());
+        self.outcome = outcome;
+    }
+}
+
+/// The last traces of each automation of an engine, see `AutomationEngine::traces`
+///
+/// The clones share the traces.
+#[derive(Debug, Clone, Default)]
+pub struct Traces {
+    traces: Arc<Mutex<HashMap<String, VecDeque<Trace>>>>,
+}
+
+impl Traces {
+    /// The last traces of an automation, the most recent last
+    pub fn get(&self, automation: &str) -> Vec<Trace> {
+        self.traces
+            .lock()
+            .get(automation)
+            .map(|traces| traces.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn push(&self, trace: Trace) {
+        let mut traces = self.traces.lock();
+        let traces = traces.entry(trace.automation.clone()).or_default();
+        if traces.len() == TRACES_KEPT {
+            traces.pop_front();
+        }
+        traces.push_back(trace);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::scheduler::Cron;
+use crate::types::{EntityId, HassEntity, HassEvent};
+
+use chrono::{DateTime, NaiveTime, TimeDelta, Utc};
+use serde_json::Value;
+use std::time::Duration;
+
+/// What starts a run of an automation
+///
+/// [Triggers](https://www.home-assistant.io/docs/automation/trigger/)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// The state of an entity changed, optionally from and to the given states, and stayed so
+    /// for `for_`
+    ///
+    /// Without `from` nor `to` the changes of the attributes fire the trigger too.
+    State {
+        entity_id: EntityId,
+        from: Option<String>,
+        to: Option<String>,
+        for_: Option<Duration>,
+    },
+    /// An event was fired, its data containing the fields of `data` if set
+    Event {
+        event_type: String,
+        data: Option<Value>,
+    },
+    /// Every day at a time of the time zone of the server
+    Time { at: NaiveTime },
+    /// Periodically, starting from the start of the engine
+    Interval { every: Duration },
+    /// At a sun event moved by `offset`, computed at the location of the server
+    Sun { event: SunEvent, offset: TimeDelta },
+    /// On a cron expression in the time zone of the server
+    Cron(Cron),
+}
+
+pub use crate::scheduler::SunEvent;
+
+/// What fired a run, see `Trace::trigger`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriggerData {
+    /// the index of the trigger in `Automation::triggers`
+    pub index: usize,
+    /// the entity of a `State` trigger
+    pub entity_id: Option<EntityId>,
+    pub from_state: Option<HassEntity>,
+    pub to_state: Option<HassEntity>,
+    /// the event of an `Event` trigger
+    pub event: Option<HassEvent>,
+    pub fired_at: DateTime<Utc>,
+}
+
+impl TriggerData {
+    pub(crate) fn new(index: usize) -> Self {
+        Self {
+            index,
+            entity_id: None,
+            from_state: None,
+            to_state: None,
+            event: None,
+            fired_at: super::now(),
+        }
+    }
+}
+
+impl Trigger {
+    /// Whether the change of a state fires a `State` trigger, the `for_` delay aside
+    pub fn matches_state(&self, old: Option<&HassEntity>, new: Option<&HassEntity>) -> bool {
+        let Self::State {
+            entity_id,
+            from,
+            to,
+            ..
+        } = self
+        else {
+            return false;
+        };
+        if new.or(old).map(|entity| &entity.entity_id) != Some(entity_id) {
+            return false;
+        }
+
+        let old_state = old.map(|entity| entity.state.as_str());
+        let new_state = new.map(|entity| entity.state.as_str());
+        if (from.is_some() || to.is_some()) && old_state == new_state {
+            // only the attributes changed
+            return false;
+        }
+        from.as_deref().is_none_or(|from| old_state == Some(from))
+            && to.as_deref().is_none_or(|to| new_state == Some(to))
+    }
+
+    /// Whether an event fires an `Event` trigger
+    pub fn matches_event(&self, event: &HassEvent) -> bool {
+        let Self::Event { event_type, data } = self else {
+            return false;
+        };
+        if *event_type != event.event_type {
+            return false;
+        }
+        match data {
+            Some(pattern) => serde_json::to_value(&event.data)
+                .map(|data| contains(&data, pattern))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+/// whether `value` has the fields of `pattern`, recursively
+fn contains(value: &Value, pattern: &Value) -> bool {
+    match (value, pattern) {
+        (Value::Object(value), Value::Object(pattern)) => pattern
+            .iter()
+            .all(|(key, pattern)| value.get(key).is_some_and(|value| contains(value, pattern))),
+        _ => value == pattern,
+    }
+}
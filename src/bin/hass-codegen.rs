@@ -0,0 +1,43 @@
+//! Generates the typed service bindings from a `get_services` JSON dump
+//!
+//! Usage: `hass-codegen <services.json> [output.rs]`, printing to stdout when no output is given.
+//! The dump is either the services map or the whole websocket result message.
+
+use hass_rs::codegen::generate;
+use hass_rs::HassServices;
+use serde_json::Value;
+use std::{env, fs, process};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(input) = args.first() else {
+        eprintln!("usage: hass-codegen <services.json> [output.rs]");
+        process::exit(2);
+    };
+
+    let dump = fs::read_to_string(input).unwrap_or_else(|err| {
+        eprintln!("unable to read {input}: {err}");
+        process::exit(1);
+    });
+    let mut value: Value = serde_json::from_str(&dump).unwrap_or_else(|err| {
+        eprintln!("unable to parse {input}: {err}");
+        process::exit(1);
+    });
+    // {"id": 1, "type": "result", "success": true, "result": {...}}
+    if value["type"] == "result" {
+        value = value["result"].take();
+    }
+    let services: HassServices = serde_json::from_value(value).unwrap_or_else(|err| {
+        eprintln!("unexpected services format: {err}");
+        process::exit(1);
+    });
+
+    let code = generate(&services);
+    match args.get(1) {
+        Some(output) => fs::write(output, code).unwrap_or_else(|err| {
+            eprintln!("unable to write {output}: {err}");
+            process::exit(1);
+        }),
+        None => print!("{code}"),
+    }
+}
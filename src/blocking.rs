@@ -0,0 +1,412 @@
+//! Synchronous client, for the applications not running an async executor
+//!
+//! `blocking::HassClient` wraps the async client and runs each call to completion on an
+//! internal runtime, a tokio runtime with a single worker thread with the `tokio-runtime`
+//! feature, or the global executor of async-std or smol. The connection tasks keep running
+//! between the calls, so the subscriptions are delivered as `std::sync::mpsc` receivers.
+//!
+//! It must not be used from within an async context, where the blocking calls would stall
+//! the executor.
+//!
+//! ```no_run
+//! use hass_rs::blocking::HassClient;
+//!
+//! # fn main() -> hass_rs::HassResult<()> {
+//! let mut client = HassClient::new("ws://localhost:8123/api/websocket")?;
+//! client.auth_with_longlivedtoken("my_token")?;
+//!
+//! let states = client.get_states()?;
+//! println!("{} entities", states.len());
+//!
+//! for event in client.subscribe_event("state_changed")?.iter() {
+//!     println!("{:?}", event.event.data.entity_id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::command::{HassCommand, OneShot, Subscription};
+use crate::metrics::HassMetrics;
+use crate::runtime::BlockingRuntime;
+use crate::services::ServiceCall;
+use crate::types::{
+    ConfigEntryChange, CoreConfigUpdate, FlowInProgress, HassConfig, HassConfigEntry, HassDomain,
+    HassEntity, HassPanels, HassRegistryArea, HassRegistryDevice, HassRegistryEntity,
+    HassRegistryFloor, HassRegistryLabel, HassServices, HassStatisticDuringPeriod,
+    HassStatisticMetadata, HassStatistics, IntegrationType, LogbookEntry, LogbookStreamMessage,
+    ServerInfo, StatisticImportMetadata, StatisticImportValue, StatisticType, StatisticWindow,
+    StatisticsPeriod, WSEvent,
+};
+use crate::{HassIssueEvent, HassIssues, HassResult, HassSubscription};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The synchronous counterpart of `crate::HassClient`, see the module documentation
+///
+/// Dropping a subscription receiver unsubscribes on the next message received from the server,
+/// the same way as with the async client.
+pub struct HassClient {
+    // dropped before the runtime running its tasks
+    client: crate::HassClient,
+    runtime: BlockingRuntime,
+}
+
+#[allow(clippy::result_large_err)]
+impl HassClient {
+    pub fn new(url: &str) -> HassResult<Self> {
+        let runtime = BlockingRuntime::new()?;
+        let client = runtime.block_on(crate::HassClient::new(url))?;
+        Ok(Self { client, runtime })
+    }
+
+    /// Same as `new`, reporting the health of the connection to `metrics`
+    pub fn with_metrics(url: &str, metrics: Arc<dyn HassMetrics>) -> HassResult<Self> {
+        let runtime = BlockingRuntime::new()?;
+        let client = runtime.block_on(crate::HassClient::with_metrics(url, metrics))?;
+        Ok(Self { client, runtime })
+    }
+
+    /// Sets how long to wait for the response of a command before failing with
+    /// `HassError::Timeout`, by default the client waits forever
+    pub fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.client.set_request_timeout(timeout);
+    }
+
+    /// The version of the Home Assistant the client is connected to
+    pub fn server_info(&self) -> Option<ServerInfo> {
+        self.client.server_info()
+    }
+
+    /// authenticate the session using a long-lived access token
+    pub fn auth_with_longlivedtoken(&mut self, token: &str) -> HassResult<()> {
+        self.runtime
+            .block_on(self.client.auth_with_longlivedtoken(token))
+    }
+
+    /// This will close the connection gracefully, see `crate::HassClient::close`
+    ///
+    /// Dropping the client closes it the same way, waiting for the close to complete.
+    pub fn close(&mut self) {
+        self.runtime.block_on(self.client.close());
+    }
+
+    /// The heartbeat ensuring the connection is still alive
+    pub fn ping(&mut self) -> HassResult<()> {
+        self.runtime.block_on(self.client.ping())
+    }
+
+    /// This will get the current config of the Home Assistant.
+    pub fn get_config(&mut self) -> HassResult<HassConfig> {
+        self.runtime.block_on(self.client.get_config())
+    }
+
+    /// This will change the core config, only the fields set in `update` are changed.
+    pub fn update_core_config(&mut self, update: CoreConfigUpdate) -> HassResult<()> {
+        self.runtime
+            .block_on(self.client.update_core_config(update))
+    }
+
+    /// This will get all the current states from Home Assistant.
+    pub fn get_states(&mut self) -> HassResult<Vec<HassEntity>> {
+        self.runtime.block_on(self.client.get_states())
+    }
+
+    /// This will get all the services from Home Assistant.
+    pub fn get_services(&mut self) -> HassResult<HassServices> {
+        self.runtime.block_on(self.client.get_services())
+    }
+
+    /// This will get all the registered panels from Home Assistant.
+    pub fn get_panels(&mut self) -> HassResult<HassPanels> {
+        self.runtime.block_on(self.client.get_panels())
+    }
+
+    /// This will get the current area registry list from Home Assistant.
+    pub fn get_area_registry_list(&mut self) -> HassResult<Vec<HassRegistryArea>> {
+        self.runtime.block_on(self.client.get_area_registry_list())
+    }
+
+    /// This will get the current floor registry list from Home Assistant.
+    pub fn get_floor_registry_list(&mut self) -> HassResult<Vec<HassRegistryFloor>> {
+        self.runtime.block_on(self.client.get_floor_registry_list())
+    }
+
+    /// This will get the current label registry list from Home Assistant.
+    pub fn get_label_registry_list(&mut self) -> HassResult<Vec<HassRegistryLabel>> {
+        self.runtime.block_on(self.client.get_label_registry_list())
+    }
+
+    /// This will get the current device registry list from Home Assistant.
+    pub fn get_device_registry_list(&mut self) -> HassResult<Vec<HassRegistryDevice>> {
+        self.runtime
+            .block_on(self.client.get_device_registry_list())
+    }
+
+    /// This will get the current entity registry list from Home Assistant.
+    pub fn get_entity_registry_list(&mut self) -> HassResult<Vec<HassRegistryEntity>> {
+        self.runtime
+            .block_on(self.client.get_entity_registry_list())
+    }
+
+    /// This will call a service in Home Assistant.
+    pub fn call_service(
+        &mut self,
+        domain: impl Into<HassDomain>,
+        service: String,
+        service_data: Option<Value>,
+    ) -> HassResult<()> {
+        self.runtime
+            .block_on(self.client.call_service(domain, service, service_data))
+    }
+
+    /// This will call a service built with one of the typed requests of the `services` module.
+    pub fn call_service_typed<S: ServiceCall>(&mut self, call: &S) -> HassResult<()> {
+        self.runtime.block_on(self.client.call_service_typed(call))
+    }
+
+    /// This will fire an event on the event bus of Home Assistant.
+    pub fn fire_event(&mut self, event_type: &str, event_data: Option<Value>) -> HassResult<()> {
+        self.runtime
+            .block_on(self.client.fire_event(event_type, event_data))
+    }
+
+    /// This will render a template with the current states.
+    pub fn render_template(&mut self, template: &str) -> HassResult<String> {
+        self.runtime.block_on(self.client.render_template(template))
+    }
+
+    /// The command subscribe_event will subscribe your client to the event bus.
+    ///
+    /// Returns a receiver that will receive the subscription messages.
+    pub fn subscribe_event(&mut self, event_name: &str) -> HassResult<Receiver<WSEvent>> {
+        let mut events = self
+            .runtime
+            .block_on(self.client.subscribe_event(event_name))?;
+
+        let (tx, rx) = channel();
+        self.runtime.spawn(async move {
+            while let Some(event) = events.recv().await {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    /// This will unsubscribe from an event subscription.
+    pub fn unsubscribe_event(&mut self, subscription_id: u64) -> HassResult<()> {
+        self.runtime
+            .block_on(self.client.unsubscribe_event(subscription_id))
+    }
+
+    /// Lists pending issues of the Home Assistant instance.
+    pub fn list_issues(&mut self) -> HassResult<HassIssues> {
+        self.runtime.block_on(self.client.list_issues())
+    }
+
+    /// This will ignore a repair issue, or unignore it when `ignore` is false.
+    pub fn ignore_issue(&mut self, domain: &str, issue_id: &str, ignore: bool) -> HassResult<()> {
+        self.runtime
+            .block_on(self.client.ignore_issue(domain, issue_id, ignore))
+    }
+
+    /// Subscribes to the repair issues changes.
+    pub fn subscribe_issues(&mut self) -> HassResult<Receiver<HassResult<HassIssueEvent>>> {
+        let subscription = self.runtime.block_on(self.client.subscribe_issues())?;
+        Ok(self.forward(subscription))
+    }
+
+    /// Lists the ids of the long-term statistics, optionally only the ones having a `mean` or a `sum`.
+    pub fn list_statistic_ids(
+        &mut self,
+        statistic_type: Option<StatisticType>,
+    ) -> HassResult<Vec<HassStatisticMetadata>> {
+        self.runtime
+            .block_on(self.client.list_statistic_ids(statistic_type))
+    }
+
+    /// This will get the metadata of the provided statistic ids, or of all statistics if `None`.
+    pub fn get_statistics_metadata(
+        &mut self,
+        statistic_ids: Option<Vec<String>>,
+    ) -> HassResult<Vec<HassStatisticMetadata>> {
+        self.runtime
+            .block_on(self.client.get_statistics_metadata(statistic_ids))
+    }
+
+    /// This will get the long-term statistics series of the provided statistic ids.
+    pub fn statistics_during_period(
+        &mut self,
+        start_time: &str,
+        end_time: Option<&str>,
+        statistic_ids: Vec<String>,
+        period: StatisticsPeriod,
+        types: Vec<StatisticType>,
+        units: Option<HashMap<String, String>>,
+    ) -> HassResult<HassStatistics> {
+        self.runtime.block_on(self.client.statistics_during_period(
+            start_time,
+            end_time,
+            statistic_ids,
+            period,
+            types,
+            units,
+        ))
+    }
+
+    /// This will aggregate a single statistic over the provided window.
+    pub fn statistic_during_period(
+        &mut self,
+        statistic_id: &str,
+        types: Vec<StatisticType>,
+        units: Option<HashMap<String, String>>,
+        window: Option<StatisticWindow>,
+    ) -> HassResult<HassStatisticDuringPeriod> {
+        self.runtime.block_on(self.client.statistic_during_period(
+            statistic_id,
+            types,
+            units,
+            window,
+        ))
+    }
+
+    /// This will import (or overwrite) long-term statistics.
+    pub fn import_statistics(
+        &mut self,
+        metadata: StatisticImportMetadata,
+        stats: Vec<StatisticImportValue>,
+    ) -> HassResult<()> {
+        self.runtime
+            .block_on(self.client.import_statistics(metadata, stats))
+    }
+
+    /// This will get the logbook entries of the provided time window.
+    pub fn get_logbook_events(
+        &mut self,
+        start_time: &str,
+        end_time: Option<&str>,
+        entity_ids: Option<Vec<String>>,
+        device_ids: Option<Vec<String>>,
+    ) -> HassResult<Vec<LogbookEntry>> {
+        self.runtime.block_on(
+            self.client
+                .get_logbook_events(start_time, end_time, entity_ids, device_ids),
+        )
+    }
+
+    /// The command logbook/event_stream will stream the logbook entries starting at `start_time`.
+    pub fn subscribe_logbook(
+        &mut self,
+        start_time: &str,
+        end_time: Option<&str>,
+        entity_ids: Option<Vec<String>>,
+        device_ids: Option<Vec<String>>,
+    ) -> HassResult<Receiver<HassResult<LogbookStreamMessage>>> {
+        let subscription = self.runtime.block_on(
+            self.client
+                .subscribe_logbook(start_time, end_time, entity_ids, device_ids),
+        )?;
+        Ok(self.forward(subscription))
+    }
+
+    /// This will get the config entries, optionally filtered by integration type and domain.
+    pub fn get_config_entries(
+        &mut self,
+        type_filter: Option<Vec<IntegrationType>>,
+        domain: Option<&str>,
+    ) -> HassResult<Vec<HassConfigEntry>> {
+        self.runtime
+            .block_on(self.client.get_config_entries(type_filter, domain))
+    }
+
+    /// The command config_entries/subscribe will notify about the config entries changes.
+    pub fn subscribe_config_entries(
+        &mut self,
+        type_filter: Option<Vec<IntegrationType>>,
+    ) -> HassResult<Receiver<HassResult<Vec<ConfigEntryChange>>>> {
+        let subscription = self
+            .runtime
+            .block_on(self.client.subscribe_config_entries(type_filter))?;
+        Ok(self.forward(subscription))
+    }
+
+    /// This will disable a config entry, returns true if Home Assistant has to be restarted.
+    pub fn disable_config_entry(&mut self, entry_id: &str) -> HassResult<bool> {
+        self.runtime
+            .block_on(self.client.disable_config_entry(entry_id))
+    }
+
+    /// This will enable a config entry, returns true if Home Assistant has to be restarted.
+    pub fn enable_config_entry(&mut self, entry_id: &str) -> HassResult<bool> {
+        self.runtime
+            .block_on(self.client.enable_config_entry(entry_id))
+    }
+
+    /// This will reload a config entry, e.g. one that failed to set up.
+    pub fn reload_config_entry(&mut self, entry_id: &str) -> HassResult<()> {
+        self.runtime
+            .block_on(self.client.reload_config_entry(entry_id))
+    }
+
+    /// Lists the config flows in progress.
+    pub fn list_config_flows(&mut self) -> HassResult<Vec<FlowInProgress>> {
+        self.runtime.block_on(self.client.list_config_flows())
+    }
+
+    /// This will send any command not modeled by this library and return its raw result.
+    pub fn send_raw(&mut self, msg_type: &str, payload: Value) -> HassResult<Value> {
+        self.runtime
+            .block_on(self.client.send_raw(msg_type, payload))
+    }
+
+    /// This will send a one-shot command implementing `HassCommand` and return its result.
+    pub fn send<C: HassCommand<Kind = OneShot>>(
+        &mut self,
+        request: C::Request,
+    ) -> HassResult<C::Response> {
+        self.runtime.block_on(self.client.send::<C>(request))
+    }
+
+    /// This will send a subscription command implementing `HassCommand`.
+    ///
+    /// Returns a receiver that will receive the decoded events.
+    pub fn subscribe<C>(
+        &mut self,
+        request: C::Request,
+    ) -> HassResult<Receiver<HassResult<C::Response>>>
+    where
+        C: HassCommand<Kind = Subscription>,
+        C::Response: 'static,
+    {
+        let subscription = self.runtime.block_on(self.client.send::<C>(request))?;
+        Ok(self.forward(subscription))
+    }
+
+    /// forwards the messages of a subscription until the receiver is dropped
+    fn forward<T: DeserializeOwned + Send + 'static>(
+        &self,
+        mut subscription: HassSubscription<T>,
+    ) -> Receiver<HassResult<T>> {
+        let (tx, rx) = channel();
+        self.runtime.spawn(async move {
+            while let Some(message) = subscription.recv().await {
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        rx
+    }
+}
+
+impl Drop for HassClient {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
@@ -1,50 +1,109 @@
 //! Home Assistant client implementation
 
+use crate::command::{CommandKind, HassCommand};
+use crate::metrics::{HassMetrics, RequestOutcome};
+use crate::protocol::{Outcome, Protocol};
+use crate::services::ServiceCall;
 use crate::types::{
-    Ask, Auth, CallService, Command, HassConfig, HassEntity, HassPanels, HassRegistryArea,
-    HassRegistryDevice, HassRegistryEntity, HassServices, Response, Subscribe, Unsubscribe,
-    WSEvent,
+    Ask, Auth, CallService, Command, ConfigEntryChange, CoreConfigUpdate, DisableConfigEntry,
+    FlowInProgress, GetConfigEntries, GetStatisticsMetadata, HaVersion, HassConfig,
+    HassConfigEntry, HassDomain, HassEntity, HassPanels, HassRegistryArea, HassRegistryDevice,
+    HassRegistryEntity, HassRegistryFloor, HassRegistryLabel, HassServices,
+    HassStatisticDuringPeriod, HassStatisticMetadata, HassStatistics, IgnoreIssue,
+    ImportStatistics, IntegrationType, ListStatisticIds, Logbook, LogbookEntry,
+    LogbookStreamMessage, RawCommand, Response, ServerInfo, StatisticDuringPeriod,
+    StatisticImportMetadata, StatisticImportValue, StatisticType, StatisticWindow,
+    StatisticsDuringPeriod, StatisticsPeriod, Subscribe, SubscribeConfigEntries, Target,
+    Unsubscribe, UpdateCoreConfig, WSEvent, WithRaw,
+};
+use crate::{
+    runtime, EventReceiver, HassError, HassIssueEvent, HassIssues, HassResult, HassSubscription,
 };
-use crate::{HassError, HassIssues, HassResult};
 
-use futures_util::{stream::SplitStream, SinkExt, StreamExt};
+use async_channel::{bounded as channel, Sender};
+use futures_channel::oneshot::{
+    channel as oneshot, Receiver as OneShotReceiver, Sender as OneShotSender,
+};
+use futures_util::future::{select, Either};
+use futures_util::{SinkExt, Stream, StreamExt};
 use parking_lot::Mutex;
-use serde_json::Value;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tokio::sync::oneshot::{channel as oneshot, Sender as OneShotSender};
-use tokio_tungstenite::tungstenite::{Error, Message};
-use tokio_tungstenite::{connect_async, WebSocketStream};
+use std::time::{Duration, Instant};
+use tungstenite::{Error, Message};
 
 /// HassClient is a library that is meant to simplify the conversation with HomeAssistant Web Socket Server
 /// it provides a number of convenient functions that creates the requests and read the messages from server
+///
+/// It drives a `protocol::Protocol` over the websocket, the protocol itself holds no IO.
 pub struct HassClient {
-    // holds the id of the WS message
-    last_sequence: Arc<AtomicU64>,
-
     rx_state: Arc<ReceiverState>,
 
     /// Client --> Gateway (send "Commands" msg to the Gateway)
     message_tx: Arc<Sender<Message>>,
+
+    /// how long to wait for the response of a command, forever if `None`
+    request_timeout: Option<Duration>,
+
+    /// the background tasks, `None` once the client is closed
+    tasks: Option<Tasks>,
+}
+
+/// the first release with the floor and label registries
+const FLOORS_AND_LABELS: HaVersion = HaVersion::new(2024, 4, 0);
+
+/// how long `close` waits for the server to acknowledge the close frame
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// the handles of the writer and reader tasks, a task is done once its `done` receiver resolves
+struct Tasks {
+    // stops the reader task when dropped
+    shutdown: OneShotSender<()>,
+    writer_done: OneShotReceiver<()>,
+    reader_done: OneShotReceiver<()>,
 }
 
+/// the type reported to the metrics for the events of a subscription which is gone
+const UNKNOWN_TYPE: &str = "unknown";
+
+/// the protocol state, and the channels of its pending requests and subscriptions
 #[derive(Default)]
 struct ReceiverState {
+    protocol: Mutex<Protocol>,
+    metrics: Option<Arc<dyn HassMetrics>>,
     subscriptions: Mutex<HashMap<u64, Sender<WSEvent>>>,
+    raw_subscriptions: Mutex<HashMap<u64, Sender<Value>>>,
     pending_requests: Mutex<HashMap<u64, OneShotSender<Response>>>,
     untagged_request: Mutex<Option<OneShotSender<Response>>>,
+    // set by close or drop, the requests then fail with ClientClosed
+    closed: AtomicBool,
+    // from auth_required, then from auth_ok
+    server_info: Mutex<Option<ServerInfo>>,
 }
 
 impl ReceiverState {
+    fn metrics(&self) -> &dyn HassMetrics {
+        match &self.metrics {
+            Some(metrics) => metrics.as_ref(),
+            None => &NoMetrics,
+        }
+    }
+
     fn get_tx(self: &Arc<Self>, id: u64) -> Option<Sender<WSEvent>> {
-        self.subscriptions.lock().get(&id).map(|tx| tx.clone())
+        self.subscriptions.lock().get(&id).cloned()
+    }
+
+    fn get_raw_tx(self: &Arc<Self>, id: u64) -> Option<Sender<Value>> {
+        self.raw_subscriptions.lock().get(&id).cloned()
     }
 
     fn rm_subscription(self: &Arc<Self>, id: u64) {
+        self.protocol.lock().forget_subscription(id);
         self.subscriptions.lock().remove(&id);
+        self.raw_subscriptions.lock().remove(&id);
     }
 
     fn take_responder(self: &Arc<Self>, id: u64) -> Option<OneShotSender<Response>> {
@@ -54,134 +113,313 @@ impl ReceiverState {
     fn take_untagged(self: &Arc<Self>) -> Option<OneShotSender<Response>> {
         self.untagged_request.lock().take()
     }
+
+    fn set_server_info(&self, ha_version: &str) {
+        match ha_version.parse() {
+            Ok(ha_version) => *self.server_info.lock() = Some(ServerInfo { ha_version }),
+            Err(err) => log::warn!("{err}"),
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// marks the client closed, ending the subscriptions and failing the pending requests
+    ///
+    /// Returns the frames unsubscribing the active subscriptions.
+    fn shutdown(self: &Arc<Self>) -> Vec<Message> {
+        self.closed.store(true, Ordering::Relaxed);
+
+        let mut ids: Vec<u64> = self
+            .subscriptions
+            .lock()
+            .drain()
+            .map(|(id, _)| id)
+            .collect();
+        ids.extend(self.raw_subscriptions.lock().drain().map(|(id, _)| id));
+        ids.sort_unstable();
+
+        let frames = {
+            let mut protocol = self.protocol.lock();
+            let mut frames = Vec::new();
+            for id in ids {
+                if let Ok((unsubscribe_id, frame)) = protocol.unsubscribe(id) {
+                    protocol.cancel(unsubscribe_id);
+                    frames.push(Message::text(frame));
+                }
+            }
+            frames
+        };
+
+        // the dropped responders fail the requests with ClientClosed
+        self.pending_requests.lock().clear();
+        self.untagged_request.lock().take();
+        frames
+    }
 }
 
 async fn ws_incoming_messages(
-    mut stream: SplitStream<WebSocketStream<impl AsyncRead + AsyncWrite + Unpin>>,
+    mut stream: impl Stream<Item = Result<Message, Error>> + Unpin,
     rx_state: Arc<ReceiverState>,
     message_tx: Arc<Sender<Message>>,
-    last_sequence: Arc<AtomicU64>,
+    mut shutdown: OneShotReceiver<()>,
 ) {
-    while let Some(message) = stream.next().await {
+    let mut close_reason = String::new();
+
+    loop {
+        let message = match select(stream.next(), &mut shutdown).await {
+            Either::Left((Some(message), _)) => message,
+            // the connection is gone, or the client was closed or dropped
+            _ => break,
+        };
         log::trace!("incoming: {message:#?}");
-        match check_if_event(message) {
-            Ok(event) => {
-                // Dispatch to subscriber
-                let id = event.id;
-                if let Some(tx) = rx_state.get_tx(id) {
-                    if tx.send(event).await.is_err() {
-                        rx_state.rm_subscription(id);
-                        let unsub_id = last_sequence.fetch_add(1, Ordering::Relaxed);
-                        let cmd = Command::Unsubscribe(Unsubscribe {
-                            id: unsub_id,
-                            msg_type: "unsubscribe_events".to_owned(),
-                            subscription: id,
-                        });
-                        let _ = message_tx.send(cmd.to_tungstenite_message()).await;
-                    }
-                }
-            }
-            Err(message) => match message {
-                Ok(Message::Text(data)) => {
-                    let payload: Result<Response, HassError> = serde_json::from_str(data.as_str())
-                        .map_err(|err| HassError::UnableToDeserialize(err));
-
-                    match payload {
-                        Ok(response) => match response.id() {
-                            Some(id) => {
-                                if let Some(tx) = rx_state.take_responder(id) {
-                                    tx.send(response).ok();
-                                } else {
-                                    log::error!("no responder for id={id} {response:#?}");
-                                }
-                            }
-                            None => {
-                                if matches!(&response, Response::AuthRequired(_)) {
-                                    // AuthRequired is always sent unilaterally at connect time.
-                                    // It is never a response to one of our commands, so the
-                                    // simplest way to deal with it is to ignore it.
-                                    log::trace!("Ignoring {response:?}");
-                                    continue;
-                                }
-
-                                if let Some(tx) = rx_state.take_untagged() {
-                                    tx.send(response).ok();
-                                } else {
-                                    log::error!("no untagged responder for {response:#?}");
-                                }
-                            }
-                        },
-                        Err(err) => {
-                            log::error!("Error deserializing response: {err:#} {data}");
+
+        match message {
+            Ok(Message::Text(data)) => {
+                let outcomes = rx_state.protocol.lock().receive(data.as_str());
+
+                match outcomes {
+                    Ok(outcomes) => {
+                        for outcome in outcomes {
+                            dispatch(&rx_state, &message_tx, outcome).await;
                         }
                     }
-                }
-                Ok(Message::Ping(data)) => {
-                    if let Err(err) = message_tx.send(Message::Pong(data)).await {
-                        log::error!("Error responding to ping: {err:#}");
-                        break;
+                    Err(err) => {
+                        rx_state.metrics().deserialization_failed();
+                        log::error!("Error deserializing response: {err:#} {data}");
                     }
                 }
-                Ok(Message::Close(frame)) => {
-                    log::info!("Close message received: {:?}", frame);
+            }
+            Ok(Message::Ping(data)) => {
+                if let Err(err) = message_tx.send(Message::Pong(data)).await {
+                    log::error!("Error responding to ping: {err:#}");
+                    break;
+                }
+            }
+            Ok(Message::Close(frame)) => {
+                log::info!("Close message received: {:?}", frame);
+
+                close_reason = frame.map_or_else(String::new, |f| f.reason.to_string());
+                break;
+            }
+            unexpected => log::error!("Unexpected message: {unexpected:#?}"),
+        }
+    }
+
+    // the requests still pending will never get their response
+    let outcomes = rx_state.protocol.lock().close(&close_reason);
+    for outcome in outcomes {
+        dispatch(&rx_state, &message_tx, outcome).await;
+    }
 
-                    let close_reason = frame.map_or_else(String::new, |f| f.reason.to_string());
+    // the subscription receivers return None once their sender is dropped
+    rx_state.subscriptions.lock().clear();
+    rx_state.raw_subscriptions.lock().clear();
+}
 
-                    if let Some(tx) = rx_state.take_untagged() {
-                        tx.send(Response::Close(close_reason.clone())).ok();
+/// forward an outcome of the protocol to the channel waiting for it
+async fn dispatch(rx_state: &Arc<ReceiverState>, message_tx: &Sender<Message>, outcome: Outcome) {
+    match outcome {
+        Outcome::AuthRequired { ha_version } => {
+            // AuthRequired is always sent unilaterally at connect time,
+            // it is never a response to one of our commands.
+            log::trace!("auth_required, ha_version={ha_version}");
+            rx_state.set_server_info(&ha_version);
+        }
+        Outcome::Response {
+            id: Some(id),
+            response,
+        } => {
+            if let Some(tx) = rx_state.take_responder(id) {
+                tx.send(response).ok();
+            } else {
+                log::error!("no responder for id={id} {response:#?}");
+            }
+        }
+        Outcome::Response { id: None, response } => {
+            if let Some(tx) = rx_state.take_untagged() {
+                tx.send(response).ok();
+            } else {
+                log::error!("no untagged responder for {response:#?}");
+            }
+        }
+        Outcome::Event {
+            subscription,
+            event,
+        } => {
+            let msg_type = rx_state
+                .protocol
+                .lock()
+                .subscription_type(subscription)
+                .unwrap_or(UNKNOWN_TYPE)
+                .to_owned();
+            rx_state.metrics().event_received(&msg_type);
+
+            let delivered = if let Some(tx) = rx_state.get_raw_tx(subscription) {
+                tx.send(event).await.is_ok()
+            } else if let Some(tx) = rx_state.get_tx(subscription) {
+                match serde_json::from_value(event) {
+                    Ok(event) => {
+                        let event = WSEvent {
+                            id: subscription,
+                            event,
+                        };
+                        tx.send(event).await.is_ok()
                     }
-                    let mut pending_requests = rx_state.pending_requests.lock();
-                    for (_, tx) in pending_requests.drain() {
-                        tx.send(Response::Close(close_reason.clone())).ok();
+                    Err(err) => {
+                        rx_state.metrics().deserialization_failed();
+                        log::error!("Error deserializing event: {err:#}");
+                        true
                     }
-                    break;
                 }
-                unexpected => log::error!("Unexpected message: {unexpected:#?}"),
-            },
+            } else {
+                true
+            };
+
+            // the receiver of the subscription has been dropped, so there is no point to keep it alive
+            if !delivered {
+                rx_state.metrics().event_dropped(&msg_type);
+                rx_state.rm_subscription(subscription);
+                let unsubscribe = rx_state.protocol.lock().unsubscribe(subscription);
+                if let Ok((id, frame)) = unsubscribe {
+                    rx_state.protocol.lock().cancel(id);
+                    let _ = message_tx.send(Message::text(frame)).await;
+                }
+            }
+        }
+        Outcome::UnmatchedEvent { subscription, .. } => {
+            rx_state.metrics().event_dropped(UNKNOWN_TYPE);
+            log::debug!("event of an unknown subscription, id={subscription}");
+        }
+        Outcome::Unmatched(response) => log::error!("no responder for {response:#?}"),
+        Outcome::Invalid { message, error } => {
+            rx_state.metrics().deserialization_failed();
+            log::error!("Error deserializing response: {error:#} {message}");
         }
     }
 }
 
 impl HassClient {
     pub async fn new(url: &str) -> HassResult<Self> {
-        let (wsclient, _) = connect_async(url).await?;
-        let (mut sink, stream) = wsclient.split();
-        let (message_tx, mut message_rx) = channel(20);
+        Self::connect(url, None).await
+    }
+
+    /// Same as `new`, reporting the health of the connection to `metrics`, see the `metrics` module
+    pub async fn with_metrics(url: &str, metrics: Arc<dyn HassMetrics>) -> HassResult<Self> {
+        Self::connect(url, Some(metrics)).await
+    }
+
+    async fn connect(url: &str, metrics: Option<Arc<dyn HassMetrics>>) -> HassResult<Self> {
+        let (mut sink, stream) = runtime::connect(url).await?;
+        let (message_tx, message_rx) = channel::<Message>(20);
 
         let message_tx = Arc::new(message_tx);
 
-        let rx_state = Arc::new(ReceiverState::default());
-        let last_sequence = Arc::new(AtomicU64::new(1));
+        let rx_state = Arc::new(ReceiverState {
+            metrics,
+            ..ReceiverState::default()
+        });
+        rx_state.metrics().connection_opened();
+
+        let (shutdown, shutdown_rx) = oneshot();
+        let (writer_done_tx, writer_done) = oneshot::<()>();
+        let (reader_done_tx, reader_done) = oneshot::<()>();
 
-        tokio::spawn(async move {
-            while let Some(msg) = message_rx.recv().await {
+        runtime::spawn(async move {
+            let _done = writer_done_tx;
+            while let Ok(msg) = message_rx.recv().await {
+                let closing = msg.is_close();
                 if let Err(err) = sink.send(msg).await {
                     log::error!("sink error: {err:#}");
                     break;
                 }
+                if closing {
+                    break;
+                }
             }
         });
-        tokio::spawn(ws_incoming_messages(
-            stream,
-            rx_state.clone(),
-            message_tx.clone(),
-            last_sequence.clone(),
-        ));
+        let incoming =
+            ws_incoming_messages(stream, rx_state.clone(), message_tx.clone(), shutdown_rx);
+        runtime::spawn(async move {
+            let _done = reader_done_tx;
+            incoming.await;
+        });
 
         Ok(Self {
-            last_sequence,
             rx_state,
             message_tx,
+            request_timeout: None,
+            tasks: Some(Tasks {
+                shutdown,
+                writer_done,
+                reader_done,
+            }),
         })
     }
 
+    /// This will close the connection gracefully.
+    ///
+    /// The active subscriptions are unsubscribed, their receivers then return `None`, and a close
+    /// frame is sent. The pending and later requests fail with `HassError::ClientClosed`.
+    /// Returns once the background tasks are done, after the server acknowledged the close or,
+    /// when the connection is stalled, after about two seconds at most.
+    ///
+    /// Dropping the client does the same on a best-effort basis, without waiting.
+    pub async fn close(&mut self) {
+        let Some(tasks) = self.tasks.take() else {
+            return;
+        };
+
+        let frames = self.rx_state.shutdown();
+        let mut reader_done = tasks.reader_done;
+        // the writer can be stuck on a stalled socket, so its wait is bounded like the reader's
+        let graceful = async {
+            for frame in frames {
+                let _ = self.message_tx.send(frame).await;
+            }
+            let _ = self.message_tx.send(Message::Close(None)).await;
+            let _ = tasks.writer_done.await;
+            let _ = (&mut reader_done).await;
+        };
+        if runtime::timeout(CLOSE_TIMEOUT, graceful).await.is_none() {
+            drop(tasks.shutdown);
+            let _ = runtime::timeout(CLOSE_TIMEOUT, reader_done).await;
+        }
+    }
+
+    /// Sets how long to wait for the response of a command before failing with
+    /// `HassError::Timeout`, by default the client waits forever
+    pub fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.request_timeout = timeout;
+    }
+
+    /// The version of the Home Assistant the client is connected to
+    ///
+    /// Known once the server sent `auth_required`, and refreshed by the authentication.
+    pub fn server_info(&self) -> Option<ServerInfo> {
+        self.rx_state.server_info.lock().clone()
+    }
+
+    /// Fails with `HassError::UnsupportedByServer` if the server runs a release older than
+    /// `required`, the commands are sent as is when its version is unknown
+    #[allow(clippy::result_large_err)]
+    fn require_version(&self, required: HaVersion) -> HassResult<()> {
+        match self.server_info() {
+            Some(info) if !info.supports(&required) => Err(HassError::UnsupportedByServer {
+                required,
+                actual: info.ha_version,
+            }),
+            _ => Ok(()),
+        }
+    }
+
     /// authenticate the session using a long-lived access token
     ///
     /// When a client connects to the server, the server sends out auth_required.
     /// The first message from the client should be an auth message. You can authorize with an access token.
     /// If the client supplies valid authentication, the authentication phase will complete by the server sending the auth_ok message.
     /// If the data is incorrect, the server will reply with auth_invalid message and disconnect the session.
-
     pub async fn auth_with_longlivedtoken(&mut self, token: &str) -> HassResult<()> {
         let auth_message = Command::AuthInit(Auth {
             msg_type: "auth".to_owned(),
@@ -192,7 +430,10 @@ impl HassClient {
 
         // Check if the authentication was successfully, should receive {"type": "auth_ok"}
         match response {
-            Response::AuthOk(_) => Ok(()),
+            Response::AuthOk(ok) => {
+                self.rx_state.set_server_info(&ok.ha_version);
+                Ok(())
+            }
             Response::AuthInvalid(err) => Err(HassError::AuthenticationFailed(err.message)),
             unknown => Err(HassError::UnknownPayloadReceived(unknown)),
         }
@@ -208,10 +449,14 @@ impl HassClient {
             msg_type: "ping".to_owned(),
         });
 
+        let started = Instant::now();
         let response = self.command(ping_req, Some(id)).await?;
 
         match response {
-            Response::Pong(_v) => Ok(()),
+            Response::Pong(_v) => {
+                self.rx_state.metrics().ping_rtt(started.elapsed());
+                Ok(())
+            }
             Response::Result(err) => Err(HassError::ResponseError(err)),
             unknown => Err(HassError::UnknownPayloadReceived(unknown)),
         }
@@ -221,20 +466,38 @@ impl HassClient {
     ///
     /// The server will respond with a result message containing the config.
     pub async fn get_config(&mut self) -> HassResult<HassConfig> {
+        self.get_config_raw().await?.into_typed()
+    }
+
+    /// Same as `get_config`, also returning the raw JSON result.
+    pub async fn get_config_raw(&mut self) -> HassResult<WithRaw<HassConfig>> {
         let id = self.next_seq();
 
         let config_req = Command::GetConfig(Ask {
             id,
             msg_type: "get_config".to_owned(),
         });
-        let response = self.command(config_req, Some(id)).await?;
+        let value = self.command_result(config_req, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// This will change the core config, e.g. the location, the units, the currency or the time zone.
+    ///
+    /// Only the fields set in `update` are changed, requires an admin user.
+    pub async fn update_core_config(&mut self, update: CoreConfigUpdate) -> HassResult<()> {
+        let id = self.next_seq();
+
+        let cmd = Command::UpdateCoreConfig(UpdateCoreConfig {
+            id,
+            msg_type: "config/core/update".to_owned(),
+            update,
+        });
+
+        let response = self.command(cmd, Some(id)).await?;
 
         match response {
-            Response::Result(data) => {
-                let value = data.result()?;
-                let config: HassConfig = serde_json::from_value(value)?;
-                Ok(config)
-            }
+            Response::Result(v) if v.is_ok() => Ok(()),
+            Response::Result(v) => Err(HassError::ResponseError(v)),
             unknown => Err(HassError::UnknownPayloadReceived(unknown)),
         }
     }
@@ -242,135 +505,164 @@ impl HassClient {
     /// This will get all the current states from Home Assistant.
     ///
     /// The server will respond with a result message containing the states.
-
     pub async fn get_states(&mut self) -> HassResult<Vec<HassEntity>> {
+        self.get_states_raw().await?.into_typed()
+    }
+
+    /// Same as `get_states`, also returning the raw JSON result.
+    pub async fn get_states_raw(&mut self) -> HassResult<WithRaw<Vec<HassEntity>>> {
         let id = self.next_seq();
 
         let states_req = Command::GetStates(Ask {
             id,
             msg_type: "get_states".to_owned(),
         });
-        let response = self.command(states_req, Some(id)).await?;
-
-        match response {
-            Response::Result(data) => {
-                let value = data.result()?;
-                let states: Vec<HassEntity> = serde_json::from_value(value)?;
-                Ok(states)
-            }
-            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
-        }
+        let value = self.command_result(states_req, id).await?;
+        Ok(WithRaw::new(value))
     }
 
     /// This will get all the services from Home Assistant.
     ///
     /// The server will respond with a result message containing the services.
-
     pub async fn get_services(&mut self) -> HassResult<HassServices> {
+        self.get_services_raw().await?.into_typed()
+    }
+
+    /// Same as `get_services`, also returning the raw JSON result.
+    pub async fn get_services_raw(&mut self) -> HassResult<WithRaw<HassServices>> {
         let id = self.next_seq();
         let services_req = Command::GetServices(Ask {
             id,
             msg_type: "get_services".to_owned(),
         });
-        let response = self.command(services_req, Some(id)).await?;
-
-        match response {
-            Response::Result(data) => {
-                let value = data.result()?;
-                let services: HassServices = serde_json::from_value(value)?;
-                Ok(services)
-            }
-            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
-        }
+        let value = self.command_result(services_req, id).await?;
+        Ok(WithRaw::new(value))
     }
 
     /// This will get all the registered panels from Home Assistant.
     ///
     /// The server will respond with a result message containing the current registered panels.
-
     pub async fn get_panels(&mut self) -> HassResult<HassPanels> {
+        self.get_panels_raw().await?.into_typed()
+    }
+
+    /// Same as `get_panels`, also returning the raw JSON result.
+    pub async fn get_panels_raw(&mut self) -> HassResult<WithRaw<HassPanels>> {
         let id = self.next_seq();
 
         let services_req = Command::GetPanels(Ask {
             id,
             msg_type: "get_panels".to_owned(),
         });
-        let response = self.command(services_req, Some(id)).await?;
-
-        match response {
-            Response::Result(data) => {
-                let value = data.result()?;
-                let services: HassPanels = serde_json::from_value(value)?;
-                Ok(services)
-            }
-            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
-        }
+        let value = self.command_result(services_req, id).await?;
+        Ok(WithRaw::new(value))
     }
 
     /// This will get the current area registry list from Home Assistant.
     ///
     /// The server will respond with a result message containing the area registry list.
     pub async fn get_area_registry_list(&mut self) -> HassResult<Vec<HassRegistryArea>> {
+        self.get_area_registry_list_raw().await?.into_typed()
+    }
+
+    /// Same as `get_area_registry_list`, also returning the raw JSON result.
+    pub async fn get_area_registry_list_raw(
+        &mut self,
+    ) -> HassResult<WithRaw<Vec<HassRegistryArea>>> {
         let id = self.next_seq();
 
         let area_req = Command::GetAreaRegistryList(Ask {
             id,
             msg_type: "config/area_registry/list".to_owned(),
         });
-        let response = self.command(area_req, Some(id)).await?;
+        let value = self.command_result(area_req, id).await?;
+        Ok(WithRaw::new(value))
+    }
 
-        match response {
-            Response::Result(data) => {
-                let value = data.result()?;
-                let areas: Vec<HassRegistryArea> = serde_json::from_value(value)?;
-                Ok(areas)
-            }
-            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
-        }
+    /// This will get the current floor registry list from Home Assistant.
+    ///
+    /// The floors were added in Home Assistant 2024.4.
+    pub async fn get_floor_registry_list(&mut self) -> HassResult<Vec<HassRegistryFloor>> {
+        self.get_floor_registry_list_raw().await?.into_typed()
+    }
+
+    /// Same as `get_floor_registry_list`, also returning the raw JSON result.
+    pub async fn get_floor_registry_list_raw(
+        &mut self,
+    ) -> HassResult<WithRaw<Vec<HassRegistryFloor>>> {
+        self.require_version(FLOORS_AND_LABELS)?;
+        let id = self.next_seq();
+
+        let floor_req = Command::GetFloorRegistryList(Ask {
+            id,
+            msg_type: "config/floor_registry/list".to_owned(),
+        });
+        let value = self.command_result(floor_req, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// This will get the current label registry list from Home Assistant.
+    ///
+    /// The labels were added in Home Assistant 2024.4.
+    pub async fn get_label_registry_list(&mut self) -> HassResult<Vec<HassRegistryLabel>> {
+        self.get_label_registry_list_raw().await?.into_typed()
+    }
+
+    /// Same as `get_label_registry_list`, also returning the raw JSON result.
+    pub async fn get_label_registry_list_raw(
+        &mut self,
+    ) -> HassResult<WithRaw<Vec<HassRegistryLabel>>> {
+        self.require_version(FLOORS_AND_LABELS)?;
+        let id = self.next_seq();
+
+        let label_req = Command::GetLabelRegistryList(Ask {
+            id,
+            msg_type: "config/label_registry/list".to_owned(),
+        });
+        let value = self.command_result(label_req, id).await?;
+        Ok(WithRaw::new(value))
     }
 
     /// This will get the current device registry list from Home Assistant.
     ///
     /// The server will respond with a result message containing the device registry list.
     pub async fn get_device_registry_list(&mut self) -> HassResult<Vec<HassRegistryDevice>> {
+        self.get_device_registry_list_raw().await?.into_typed()
+    }
+
+    /// Same as `get_device_registry_list`, also returning the raw JSON result.
+    pub async fn get_device_registry_list_raw(
+        &mut self,
+    ) -> HassResult<WithRaw<Vec<HassRegistryDevice>>> {
         let id = self.next_seq();
 
         let device_req = Command::GetDeviceRegistryList(Ask {
             id,
             msg_type: "config/device_registry/list".to_owned(),
         });
-        let response = self.command(device_req, Some(id)).await?;
-
-        match response {
-            Response::Result(data) => {
-                let value = data.result()?;
-                let devices: Vec<HassRegistryDevice> = serde_json::from_value(value)?;
-                Ok(devices)
-            }
-            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
-        }
+        let value = self.command_result(device_req, id).await?;
+        Ok(WithRaw::new(value))
     }
 
     /// This will get the current entity registry list from Home Assistant.
     ///
     /// The server will respond with a result message containing the entity registry list.
     pub async fn get_entity_registry_list(&mut self) -> HassResult<Vec<HassRegistryEntity>> {
+        self.get_entity_registry_list_raw().await?.into_typed()
+    }
+
+    /// Same as `get_entity_registry_list`, also returning the raw JSON result.
+    pub async fn get_entity_registry_list_raw(
+        &mut self,
+    ) -> HassResult<WithRaw<Vec<HassRegistryEntity>>> {
         let id = self.next_seq();
 
         let entity_req = Command::GetEntityRegistryList(Ask {
             id,
             msg_type: "config/entity_registry/list".to_owned(),
         });
-        let response = self.command(entity_req, Some(id)).await?;
-
-        match response {
-            Response::Result(data) => {
-                let value = data.result()?;
-                let entities: Vec<HassRegistryEntity> = serde_json::from_value(value)?;
-                Ok(entities)
-            }
-            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
-        }
+        let value = self.command_result(entity_req, id).await?;
+        Ok(WithRaw::new(value))
     }
 
     ///This will call a service in Home Assistant. Right now there is no return value.
@@ -379,12 +671,98 @@ impl HassClient {
     /// The server will indicate with a message indicating that the service is done executing.
     /// <https://developers.home-assistant.io/docs/api/websocket#calling-a-service>
     /// additional info : <https://developers.home-assistant.io/docs/api/rest> ==> Post `/api/services/<domain>/<service>`
-
     pub async fn call_service(
+        &mut self,
+        domain: impl Into<HassDomain>,
+        service: String,
+        service_data: Option<Value>,
+    ) -> HassResult<()> {
+        let domain = domain.into().to_string();
+        self.call_service_with_target(domain, service, service_data, None)
+            .await
+    }
+
+    /// This will call a service built with one of the typed requests of the `services` module.
+    ///
+    /// ```no_run
+    /// # async fn run(client: &mut hass_rs::HassClient) -> hass_rs::HassResult<()> {
+    /// use hass_rs::services::light;
+    /// use hass_rs::Target;
+    ///
+    /// let turn_on = light::TurnOn {
+    ///     brightness_pct: Some(40),
+    ///     transition: Some(2.0),
+    ///     ..light::TurnOn::new(Target::entity("light.kitchen"))
+    /// };
+    /// client.call_service_typed(&turn_on).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_service_typed<S: ServiceCall>(&mut self, call: &S) -> HassResult<()> {
+        let service_data = call.service_data()?;
+        let target = call.target().filter(|target| !target.is_empty()).cloned();
+
+        self.call_service_with_target(
+            call.domain().to_owned(),
+            call.service().to_owned(),
+            service_data,
+            target,
+        )
+        .await
+    }
+
+    /// This will fire an event on the event bus of Home Assistant.
+    pub async fn fire_event(
+        &mut self,
+        event_type: &str,
+        event_data: Option<Value>,
+    ) -> HassResult<()> {
+        let mut payload = Map::new();
+        payload.insert("event_type".to_owned(), event_type.into());
+        if let Some(event_data) = event_data {
+            payload.insert("event_data".to_owned(), event_data);
+        }
+        self.send_object("fire_event", payload).await?;
+        Ok(())
+    }
+
+    /// This will render a template with the current states.
+    ///
+    /// A result which is not a string, e.g. a number or a boolean, is returned as JSON.
+    pub async fn render_template(&mut self, template: &str) -> HassResult<String> {
+        // the server renders the template again on each change of the states it uses,
+        // only the first rendering is kept
+        #[derive(serde::Deserialize)]
+        struct Rendered {
+            result: Option<Value>,
+            error: Option<String>,
+        }
+
+        let mut payload = Map::new();
+        payload.insert("template".to_owned(), template.into());
+        payload.insert("report_errors".to_owned(), true.into());
+        let mut subscription = self
+            .subscribe_object::<Rendered>("render_template", payload)
+            .await?;
+
+        let rendered = subscription.recv().await;
+        self.unsubscribe_event(subscription.id()).await?;
+
+        let rendered = rendered.ok_or(HassError::ConnectionClosed)??;
+        match (rendered.result, rendered.error) {
+            (_, Some(error)) => Err(HassError::Generic(error)),
+            (Some(Value::String(result)), None) => Ok(result),
+            (Some(result), None) => Ok(result.to_string()),
+            (None, None) => Ok(String::new()),
+        }
+    }
+
+    pub(crate) async fn call_service_with_target(
         &mut self,
         domain: String,
         service: String,
         service_data: Option<Value>,
+        target: Option<Target>,
     ) -> HassResult<()> {
         let id = self.next_seq();
 
@@ -394,6 +772,7 @@ impl HassClient {
             domain,
             service,
             service_data,
+            target,
         });
         let response = self.command(services_req, Some(id)).await?;
 
@@ -410,8 +789,8 @@ impl HassClient {
 
     /// The command subscribe_event will subscribe your client to the event bus.
     ///
-    /// Returns a channel that will receive the subscription messages.
-    pub async fn subscribe_event(&mut self, event_name: &str) -> HassResult<Receiver<WSEvent>> {
+    /// Returns a receiver that will receive the subscription messages.
+    pub async fn subscribe_event(&mut self, event_name: &str) -> HassResult<EventReceiver> {
         let id = self.next_seq();
 
         let cmd = Command::SubscribeEvent(Subscribe {
@@ -420,43 +799,576 @@ impl HassClient {
             event_type: event_name.to_owned(),
         });
 
+        let (tx, rx) = channel(20);
+        self.rx_state.subscriptions.lock().insert(id, tx);
+
+        self.subscribe_command(cmd, id).await?;
+        Ok(EventReceiver::new(rx))
+    }
+
+    /// Lists pending issues of the Home Assistant instance.
+    pub async fn list_issues(&mut self) -> HassResult<HassIssues> {
+        self.list_issues_raw().await?.into_typed()
+    }
+
+    /// Same as `list_issues`, also returning the raw JSON result.
+    pub async fn list_issues_raw(&mut self) -> HassResult<WithRaw<HassIssues>> {
+        let id = self.next_seq();
+
+        let cmd = Command::ListRepairs(Ask {
+            id,
+            msg_type: "repairs/list_issues".to_owned(),
+        });
+
+        let value = self.command_result(cmd, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// This will ignore a repair issue, or unignore it when `ignore` is false.
+    pub async fn ignore_issue(
+        &mut self,
+        domain: &str,
+        issue_id: &str,
+        ignore: bool,
+    ) -> HassResult<()> {
+        let id = self.next_seq();
+
+        let cmd = Command::IgnoreRepair(IgnoreIssue {
+            id,
+            msg_type: "repairs/ignore_issue".to_owned(),
+            domain: domain.to_owned(),
+            issue_id: issue_id.to_owned(),
+            ignore,
+        });
+
         let response = self.command(cmd, Some(id)).await?;
 
         match response {
-            Response::Result(v) if v.is_ok() => {
-                let (tx, rx) = channel(20);
-                self.rx_state.subscriptions.lock().insert(v.id, tx);
-                return Ok(rx);
-            }
+            Response::Result(v) if v.is_ok() => Ok(()),
             Response::Result(v) => Err(HassError::ResponseError(v)),
             unknown => Err(HassError::UnknownPayloadReceived(unknown)),
         }
     }
 
-    /// Lists pending issues of the Home Assistant instance.
-    pub async fn list_issues(&mut self) -> HassResult<HassIssues> {
+    /// Subscribes to the repair issues changes, notified when an issue is created, updated or removed.
+    ///
+    /// The issue itself can be fetched with `list_issues`.
+    /// Returns a subscription that will receive the issue events.
+    pub async fn subscribe_issues(&mut self) -> HassResult<HassSubscription<HassIssueEvent>> {
         let id = self.next_seq();
 
-        let cmd = Command::ListRepairs(Ask {
+        let cmd = Command::SubscribeEvent(Subscribe {
             id,
-            msg_type: "repairs/list_issues".to_owned(),
+            msg_type: "subscribe_events".to_owned(),
+            event_type: "repairs_issue_registry_updated".to_owned(),
+        });
+
+        self.subscribe_raw(cmd, id).await
+    }
+
+    /// Lists the ids of the long-term statistics, optionally only the ones having a `mean` or a `sum`.
+    ///
+    /// The server will respond with a result message containing the statistics metadata.
+    pub async fn list_statistic_ids(
+        &mut self,
+        statistic_type: Option<StatisticType>,
+    ) -> HassResult<Vec<HassStatisticMetadata>> {
+        self.list_statistic_ids_raw(statistic_type)
+            .await?
+            .into_typed()
+    }
+
+    /// Same as `list_statistic_ids`, also returning the raw JSON result.
+    pub async fn list_statistic_ids_raw(
+        &mut self,
+        statistic_type: Option<StatisticType>,
+    ) -> HassResult<WithRaw<Vec<HassStatisticMetadata>>> {
+        let id = self.next_seq();
+
+        let cmd = Command::ListStatisticIds(ListStatisticIds {
+            id,
+            msg_type: "recorder/list_statistic_ids".to_owned(),
+            statistic_type,
+        });
+        let value = self.command_result(cmd, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// This will get the metadata of the provided statistic ids, or of all statistics if `None`.
+    ///
+    /// The server will respond with a result message containing the statistics metadata.
+    pub async fn get_statistics_metadata(
+        &mut self,
+        statistic_ids: Option<Vec<String>>,
+    ) -> HassResult<Vec<HassStatisticMetadata>> {
+        self.get_statistics_metadata_raw(statistic_ids)
+            .await?
+            .into_typed()
+    }
+
+    /// Same as `get_statistics_metadata`, also returning the raw JSON result.
+    pub async fn get_statistics_metadata_raw(
+        &mut self,
+        statistic_ids: Option<Vec<String>>,
+    ) -> HassResult<WithRaw<Vec<HassStatisticMetadata>>> {
+        let id = self.next_seq();
+
+        let cmd = Command::GetStatisticsMetadata(GetStatisticsMetadata {
+            id,
+            msg_type: "recorder/get_statistics_metadata".to_owned(),
+            statistic_ids,
+        });
+        let value = self.command_result(cmd, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// This will get the long-term statistics series of the provided statistic ids.
+    ///
+    /// `start_time` and `end_time` are ISO 8601 timestamps, `units` maps a unit class to the unit
+    /// the values should be converted to, e.g. `{"energy": "kWh"}`.
+    /// The server will respond with a result message containing the series grouped by statistic id.
+    pub async fn statistics_during_period(
+        &mut self,
+        start_time: &str,
+        end_time: Option<&str>,
+        statistic_ids: Vec<String>,
+        period: StatisticsPeriod,
+        types: Vec<StatisticType>,
+        units: Option<HashMap<String, String>>,
+    ) -> HassResult<HassStatistics> {
+        self.statistics_during_period_raw(start_time, end_time, statistic_ids, period, types, units)
+            .await?
+            .into_typed()
+    }
+
+    /// Same as `statistics_during_period`, also returning the raw JSON result.
+    pub async fn statistics_during_period_raw(
+        &mut self,
+        start_time: &str,
+        end_time: Option<&str>,
+        statistic_ids: Vec<String>,
+        period: StatisticsPeriod,
+        types: Vec<StatisticType>,
+        units: Option<HashMap<String, String>>,
+    ) -> HassResult<WithRaw<HassStatistics>> {
+        let id = self.next_seq();
+
+        let cmd = Command::StatisticsDuringPeriod(StatisticsDuringPeriod {
+            id,
+            msg_type: "recorder/statistics_during_period".to_owned(),
+            start_time: start_time.to_owned(),
+            end_time: end_time.map(str::to_owned),
+            statistic_ids,
+            period,
+            types,
+            units,
+        });
+        let value = self.command_result(cmd, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// This will aggregate a single statistic over the provided window, e.g. the energy used today.
+    ///
+    /// The server will respond with a result message containing the requested aggregates.
+    pub async fn statistic_during_period(
+        &mut self,
+        statistic_id: &str,
+        types: Vec<StatisticType>,
+        units: Option<HashMap<String, String>>,
+        window: Option<StatisticWindow>,
+    ) -> HassResult<HassStatisticDuringPeriod> {
+        self.statistic_during_period_raw(statistic_id, types, units, window)
+            .await?
+            .into_typed()
+    }
+
+    /// Same as `statistic_during_period`, also returning the raw JSON result.
+    pub async fn statistic_during_period_raw(
+        &mut self,
+        statistic_id: &str,
+        types: Vec<StatisticType>,
+        units: Option<HashMap<String, String>>,
+        window: Option<StatisticWindow>,
+    ) -> HassResult<WithRaw<HassStatisticDuringPeriod>> {
+        let id = self.next_seq();
+
+        let cmd = Command::StatisticDuringPeriod(StatisticDuringPeriod {
+            id,
+            msg_type: "recorder/statistic_during_period".to_owned(),
+            statistic_id: statistic_id.to_owned(),
+            types,
+            units,
+            window,
+        });
+        let value = self.command_result(cmd, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// This will import (or overwrite) long-term statistics, e.g. to backfill external meter data.
+    ///
+    /// The server will respond with an empty result message once the import is queued.
+    pub async fn import_statistics(
+        &mut self,
+        metadata: StatisticImportMetadata,
+        stats: Vec<StatisticImportValue>,
+    ) -> HassResult<()> {
+        let id = self.next_seq();
+
+        let cmd = Command::ImportStatistics(ImportStatistics {
+            id,
+            msg_type: "recorder/import_statistics".to_owned(),
+            metadata,
+            stats,
+        });
+        let response = self.command(cmd, Some(id)).await?;
+
+        match response {
+            Response::Result(v) if v.is_ok() => Ok(()),
+            Response::Result(v) => Err(HassError::ResponseError(v)),
+            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
+        }
+    }
+
+    /// This will get the logbook entries of the provided time window.
+    ///
+    /// `start_time` and `end_time` are ISO 8601 timestamps, the entries can be narrowed down to
+    /// some entities and devices.
+    /// The server will respond with a result message containing the logbook entries.
+    pub async fn get_logbook_events(
+        &mut self,
+        start_time: &str,
+        end_time: Option<&str>,
+        entity_ids: Option<Vec<String>>,
+        device_ids: Option<Vec<String>>,
+    ) -> HassResult<Vec<LogbookEntry>> {
+        self.get_logbook_events_raw(start_time, end_time, entity_ids, device_ids)
+            .await?
+            .into_typed()
+    }
+
+    /// Same as `get_logbook_events`, also returning the raw JSON result.
+    pub async fn get_logbook_events_raw(
+        &mut self,
+        start_time: &str,
+        end_time: Option<&str>,
+        entity_ids: Option<Vec<String>>,
+        device_ids: Option<Vec<String>>,
+    ) -> HassResult<WithRaw<Vec<LogbookEntry>>> {
+        let id = self.next_seq();
+
+        let cmd = Command::GetLogbookEvents(Logbook {
+            id,
+            msg_type: "logbook/get_events".to_owned(),
+            start_time: start_time.to_owned(),
+            end_time: end_time.map(str::to_owned),
+            entity_ids,
+            device_ids,
+        });
+        let value = self.command_result(cmd, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// The command logbook/event_stream will stream the logbook entries starting at `start_time`.
+    ///
+    /// The historical entries are sent first, then the live ones until `end_time` is reached,
+    /// or indefinitely if it is not set.
+    /// Returns a subscription that will receive the logbook messages.
+    pub async fn subscribe_logbook(
+        &mut self,
+        start_time: &str,
+        end_time: Option<&str>,
+        entity_ids: Option<Vec<String>>,
+        device_ids: Option<Vec<String>>,
+    ) -> HassResult<HassSubscription<LogbookStreamMessage>> {
+        let id = self.next_seq();
+
+        let cmd = Command::SubscribeLogbook(Logbook {
+            id,
+            msg_type: "logbook/event_stream".to_owned(),
+            start_time: start_time.to_owned(),
+            end_time: end_time.map(str::to_owned),
+            entity_ids,
+            device_ids,
+        });
+
+        self.subscribe_raw(cmd, id).await
+    }
+
+    /// This will get the config entries, optionally filtered by integration type and domain.
+    ///
+    /// The server will respond with a result message containing the config entries.
+    pub async fn get_config_entries(
+        &mut self,
+        type_filter: Option<Vec<IntegrationType>>,
+        domain: Option<&str>,
+    ) -> HassResult<Vec<HassConfigEntry>> {
+        self.get_config_entries_raw(type_filter, domain)
+            .await?
+            .into_typed()
+    }
+
+    /// Same as `get_config_entries`, also returning the raw JSON result.
+    pub async fn get_config_entries_raw(
+        &mut self,
+        type_filter: Option<Vec<IntegrationType>>,
+        domain: Option<&str>,
+    ) -> HassResult<WithRaw<Vec<HassConfigEntry>>> {
+        let id = self.next_seq();
+
+        let cmd = Command::GetConfigEntries(GetConfigEntries {
+            id,
+            msg_type: "config_entries/get".to_owned(),
+            type_filter,
+            domain: domain.map(str::to_owned),
+        });
+        let value = self.command_result(cmd, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// The command config_entries/subscribe will notify about the config entries changes,
+    /// e.g. when an entry is added, removed or its state changes to setup_retry.
+    ///
+    /// The first message lists all the current config entries.
+    /// Returns a subscription that will receive the changes.
+    pub async fn subscribe_config_entries(
+        &mut self,
+        type_filter: Option<Vec<IntegrationType>>,
+    ) -> HassResult<HassSubscription<Vec<ConfigEntryChange>>> {
+        let id = self.next_seq();
+
+        let cmd = Command::SubscribeConfigEntries(SubscribeConfigEntries {
+            id,
+            msg_type: "config_entries/subscribe".to_owned(),
+            type_filter,
         });
 
+        self.subscribe_raw(cmd, id).await
+    }
+
+    /// This will disable a config entry, unloading the integration.
+    ///
+    /// Returns true if Home Assistant has to be restarted for the change to take effect.
+    pub async fn disable_config_entry(&mut self, entry_id: &str) -> HassResult<bool> {
+        self.set_config_entry_disabled_by(entry_id, Some("user".to_owned()))
+            .await
+    }
+
+    /// This will enable a config entry previously disabled, setting the integration up.
+    ///
+    /// Returns true if Home Assistant has to be restarted for the change to take effect.
+    pub async fn enable_config_entry(&mut self, entry_id: &str) -> HassResult<bool> {
+        self.set_config_entry_disabled_by(entry_id, None).await
+    }
+
+    async fn set_config_entry_disabled_by(
+        &mut self,
+        entry_id: &str,
+        disabled_by: Option<String>,
+    ) -> HassResult<bool> {
+        let id = self.next_seq();
+
+        let cmd = Command::DisableConfigEntry(DisableConfigEntry {
+            id,
+            msg_type: "config_entries/disable".to_owned(),
+            entry_id: entry_id.to_owned(),
+            disabled_by,
+        });
         let response = self.command(cmd, Some(id)).await?;
 
         match response {
             Response::Result(data) => {
                 let value = data.result()?;
-                let issues: HassIssues = serde_json::from_value(value)?;
-                Ok(issues)
+                Ok(value["require_restart"].as_bool().unwrap_or(false))
             }
             unknown => Err(HassError::UnknownPayloadReceived(unknown)),
         }
     }
 
+    /// This will reload a config entry, e.g. one that failed to set up.
+    ///
+    /// There is no websocket command for it, so the `homeassistant.reload_config_entry` service is called.
+    /// Deleting a config entry is only exposed by the REST API, see
+    /// `HassRestClient::delete_config_entry` with the `rest` feature.
+    pub async fn reload_config_entry(&mut self, entry_id: &str) -> HassResult<()> {
+        self.call_service(
+            "homeassistant".to_owned(),
+            "reload_config_entry".to_owned(),
+            Some(serde_json::json!({ "entry_id": entry_id })),
+        )
+        .await
+    }
+
+    /// Lists the config flows in progress, e.g. the discovered integrations waiting to be set up.
+    ///
+    /// The flows themselves are driven over the REST API, see `flow::FlowDriver` (`rest` feature).
+    pub async fn list_config_flows(&mut self) -> HassResult<Vec<FlowInProgress>> {
+        self.list_config_flows_raw().await?.into_typed()
+    }
+
+    /// Same as `list_config_flows`, also returning the raw JSON result.
+    pub async fn list_config_flows_raw(&mut self) -> HassResult<WithRaw<Vec<FlowInProgress>>> {
+        let id = self.next_seq();
+
+        let cmd = Command::ListConfigFlows(Ask {
+            id,
+            msg_type: "config_entries/flow/progress".to_owned(),
+        });
+        let value = self.command_result(cmd, id).await?;
+        Ok(WithRaw::new(value))
+    }
+
+    /// send a subscription command whose events are not HassEvents
+    ///
+    /// The channel is registered before sending the command, as the server is allowed to send the
+    /// first events right after the result message.
+    async fn subscribe_raw<T: DeserializeOwned>(
+        &mut self,
+        cmd: Command,
+        id: u64,
+    ) -> HassResult<HassSubscription<T>> {
+        let (tx, rx) = channel(20);
+        self.rx_state.raw_subscriptions.lock().insert(id, tx);
+
+        self.subscribe_command(cmd, id).await?;
+        Ok(HassSubscription::new(id, rx))
+    }
+
+    /// send a subscription command whose channel is registered, removing it on failure
+    async fn subscribe_command(&mut self, cmd: Command, id: u64) -> HassResult<()> {
+        self.rx_state
+            .protocol
+            .lock()
+            .track_subscription(id, cmd.msg_type());
+
+        let response = self.command(cmd, Some(id)).await;
+
+        match response {
+            Ok(Response::Result(v)) if v.is_ok() => Ok(()),
+            Ok(Response::Result(v)) => {
+                self.rx_state.rm_subscription(id);
+                Err(HassError::ResponseError(v))
+            }
+            Ok(unknown) => {
+                self.rx_state.rm_subscription(id);
+                Err(HassError::UnknownPayloadReceived(unknown))
+            }
+            Err(err) => {
+                self.rx_state.rm_subscription(id);
+                Err(err)
+            }
+        }
+    }
+
+    /// This will send any command not modeled by this library and return its raw result.
+    ///
+    /// `payload` holds the command fields next to `type`, it has to be a JSON object, or `null`
+    /// when the command has none. The `id` is allocated by the client.
+    ///
+    /// ```no_run
+    /// # async fn run(client: &mut hass_rs::HassClient) -> hass_rs::HassResult<()> {
+    /// use serde_json::json;
+    ///
+    /// let labels = client
+    ///     .send_raw("config/label_registry/list", json!(null))
+    ///     .await?;
+    /// let floor = client
+    ///     .send_raw("config/floor_registry/create", json!({"name": "Attic"}))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_raw(&mut self, msg_type: &str, payload: Value) -> HassResult<Value> {
+        self.send_object(msg_type, to_payload(payload)?).await
+    }
+
+    /// This will send a command implementing `HassCommand`, e.g. one of a custom integration.
+    ///
+    /// A `OneShot` command returns its decoded result, a `Subscription` command returns a
+    /// `HassSubscription` receiving its events. See the `command` module.
+    pub async fn send<C: HassCommand>(
+        &mut self,
+        request: C::Request,
+    ) -> HassResult<<C::Kind as CommandKind>::Output<C::Response>> {
+        if let Some(required) = C::MIN_VERSION {
+            self.require_version(required)?;
+        }
+        let payload = to_payload(serde_json::to_value(request)?)?;
+        C::Kind::run(self, C::TYPE, payload).await
+    }
+
+    pub(crate) async fn send_object(
+        &mut self,
+        msg_type: &str,
+        payload: Map<String, Value>,
+    ) -> HassResult<Value> {
+        let id = self.next_seq();
+
+        let cmd = Command::Raw(RawCommand {
+            id,
+            msg_type: msg_type.to_owned(),
+            payload,
+        });
+        let response = self.command(cmd, Some(id)).await?;
+
+        match response {
+            Response::Result(v) if v.is_ok() => Ok(v.result().unwrap_or(Value::Null)),
+            Response::Result(v) => Err(HassError::ResponseError(v)),
+            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
+        }
+    }
+
+    pub(crate) async fn subscribe_object<T: DeserializeOwned>(
+        &mut self,
+        msg_type: &str,
+        payload: Map<String, Value>,
+    ) -> HassResult<HassSubscription<T>> {
+        let id = self.next_seq();
+
+        let cmd = Command::Raw(RawCommand {
+            id,
+            msg_type: msg_type.to_owned(),
+            payload,
+        });
+
+        self.subscribe_raw(cmd, id).await
+    }
+
+    /// send a command and return the result of its response
+    async fn command_result(&mut self, cmd: Command, id: u64) -> HassResult<Value> {
+        match self.command(cmd, Some(id)).await? {
+            Response::Result(data) => data.result(),
+            unknown => Err(HassError::UnknownPayloadReceived(unknown)),
+        }
+    }
+
     /// send commands and receive responses from the gateway
     pub(crate) async fn command(&mut self, cmd: Command, id: Option<u64>) -> HassResult<Response> {
-        let cmd_tungstenite = cmd.to_tungstenite_message();
+        let metrics = RequestMetrics::start(self.rx_state.clone(), cmd.msg_type(), id);
+
+        let request = self.request(cmd, id);
+        #[cfg(feature = "tracing")]
+        let request = tracing::Instrument::instrument(request, metrics.span.clone());
+        let response = request.await;
+
+        let outcome = match &response {
+            Ok(Response::Result(result)) if result.is_err() => RequestOutcome::Error,
+            Ok(Response::AuthInvalid(_)) => RequestOutcome::Error,
+            Ok(Response::Close(_)) => RequestOutcome::Closed,
+            Ok(_) => RequestOutcome::Success,
+            Err(HassError::ClientClosed) => RequestOutcome::Closed,
+            Err(HassError::Timeout(_)) => RequestOutcome::Timeout,
+            Err(_) => RequestOutcome::Failed,
+        };
+        metrics.finish(outcome);
+        response
+    }
+
+    /// send a command and wait for its response
+    async fn request(&mut self, cmd: Command, id: Option<u64>) -> HassResult<Response> {
+        if self.rx_state.is_closed() {
+            return Err(HassError::ClientClosed);
+        }
+        let frame = self.rx_state.protocol.lock().send_command(&cmd, id)?;
 
         let (tx, rx) = oneshot();
 
@@ -471,17 +1383,36 @@ impl HassClient {
 
         // Send the auth command to gateway
         self.message_tx
-            .send(cmd_tungstenite)
+            .send(Message::text(frame))
             .await
             .map_err(|err| HassError::SendError(err.to_string()))?;
 
-        rx.await
-            .map_err(|err| HassError::RecvError(err.to_string()))
+        let response = match self.request_timeout {
+            Some(timeout) => match runtime::timeout(timeout, rx).await {
+                Some(response) => response,
+                None => {
+                    // a late response is dropped by the protocol
+                    match id {
+                        Some(id) => {
+                            self.rx_state.protocol.lock().cancel(id);
+                            drop(self.rx_state.take_responder(id));
+                        }
+                        None => drop(self.rx_state.take_untagged()),
+                    }
+                    return Err(HassError::Timeout(timeout));
+                }
+            },
+            None => rx.await,
+        };
+        response.map_err(|err| match self.rx_state.is_closed() {
+            true => HassError::ClientClosed,
+            false => HassError::RecvError(err.to_string()),
+        })
     }
 
     /// get message sequence required by the Websocket server
     fn next_seq(&self) -> u64 {
-        self.last_sequence.fetch_add(1, Ordering::Relaxed)
+        self.rx_state.protocol.lock().next_id()
     }
 
     /// This will unsubscribe from an event subscription.
@@ -507,20 +1438,101 @@ impl HassClient {
     }
 }
 
-/// convenient function that validates if the message received is an Event
-/// the Events should be processed by used in a separate async task
-fn check_if_event(result: Result<Message, Error>) -> Result<WSEvent, Result<Message, Error>> {
-    match result {
-        Ok(Message::Text(data)) => {
-            let payload: Result<Response, HassError> =
-                serde_json::from_str(data.as_str()).map_err(|err| HassError::from(err));
-
-            if let Ok(Response::Event(event)) = payload {
-                Ok(event)
-            } else {
-                Err(Ok(Message::Text(data)))
+impl Drop for HassClient {
+    /// best-effort `close`, the frames are only sent if the writer task has room for them
+    fn drop(&mut self) {
+        if let Some(tasks) = self.tasks.take() {
+            for frame in self.rx_state.shutdown() {
+                let _ = self.message_tx.try_send(frame);
             }
+            let _ = self.message_tx.try_send(Message::Close(None));
+
+            // stops the reader task, the writer one ends after sending the close frame
+            drop(tasks);
+        }
+    }
+}
+
+/// the metrics of a client created without `with_metrics`
+struct NoMetrics;
+
+impl HassMetrics for NoMetrics {}
+
+/// reports a request to the metrics and to its span, as `Cancelled` if it is dropped before
+/// `finish`, so that every started request finishes
+struct RequestMetrics {
+    rx_state: Arc<ReceiverState>,
+    msg_type: String,
+    started: Instant,
+    finished: bool,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl RequestMetrics {
+    fn start(rx_state: Arc<ReceiverState>, msg_type: &str, id: Option<u64>) -> Self {
+        rx_state.metrics().request_started(msg_type);
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "hass.command",
+            r#type = %msg_type,
+            id = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        if let Some(id) = id {
+            span.record("id", id);
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = id;
+
+        Self {
+            rx_state,
+            msg_type: msg_type.to_owned(),
+            started: Instant::now(),
+            finished: false,
+            #[cfg(feature = "tracing")]
+            span,
         }
-        result => Err(result),
+    }
+
+    fn finish(mut self, outcome: RequestOutcome) {
+        self.finished = true;
+        self.report(outcome);
+    }
+
+    fn report(&self, outcome: RequestOutcome) {
+        let latency = self.started.elapsed();
+        self.rx_state
+            .metrics()
+            .request_finished(&self.msg_type, latency, outcome);
+
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("latency_ms", latency.as_millis() as u64);
+            self.span.record("outcome", outcome.as_str());
+        }
+    }
+}
+
+impl Drop for RequestMetrics {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.report(RequestOutcome::Cancelled);
+        }
+    }
+}
+
+/// the fields of a command sent next to `id` and `type`
+#[allow(clippy::result_large_err)]
+fn to_payload(payload: Value) -> HassResult<Map<String, Value>> {
+    match payload {
+        Value::Object(payload) => Ok(payload),
+        Value::Null => Ok(Map::new()),
+        _ => Err(HassError::Generic(
+            "the payload of a command has to be a JSON object".to_owned(),
+        )),
     }
 }
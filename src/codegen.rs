@@ -0,0 +1,239 @@
+//! Service bindings generator
+//!
+//! Generates one typed request per service from the `get_services` output, so the services of
+//! custom integrations can be called the same way as the ones of the `services` module.
+//! The field types are inferred from the `selector` of each field, the fields without a selector
+//! being kept as `serde_json::Value`.
+
+use crate::types::{Field, HassService, HassServices, Selector};
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Generates the Rust source of the service bindings, one module per domain
+///
+/// The generated code depends on `hass_rs` and `serde`.
+pub fn generate(services: &HassServices) -> String {
+    let mut out = String::new();
+    out.push_str("// Generated by hass-rs from the Home Assistant services, do not edit.\n");
+
+    let mut domains: Vec<_> = services.0.iter().collect();
+    domains.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut modules = HashSet::new();
+    for (domain, domain_services) in domains {
+        let module = unique_ident(to_ident(domain), &mut modules);
+        let _ = writeln!(out, "\npub mod {module} {{");
+        out.push_str("    #![allow(clippy::all)]\n");
+
+        let mut domain_services: Vec<_> = domain_services.iter().collect();
+        domain_services.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut types = HashSet::new();
+        for (service, hass_service) in domain_services {
+            let name = unique_type_name(to_type_name(service), &mut types);
+            generate_service(&mut out, &name, domain, service, hass_service);
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+fn generate_service(
+    out: &mut String,
+    name: &str,
+    domain: &str,
+    service: &str,
+    hass_service: &HassService,
+) {
+    out.push('\n');
+    if let Some(description) = &hass_service.description {
+        write_doc(out, "    ", description);
+    }
+    out.push_str("    #[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]\n");
+    let _ = writeln!(out, "    pub struct {name} {{");
+    // a field named like the target or like another field gets a `_` suffix
+    let mut idents = HashSet::new();
+    if hass_service.target.is_some() {
+        out.push_str("        #[serde(skip)]\n");
+        out.push_str("        pub target: hass_rs::Target,\n");
+        idents.insert("target".to_owned());
+    }
+
+    let mut fields: Vec<_> = hass_service.all_fields().collect();
+    fields.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (field_name, field) in fields {
+        let ident = unique_ident(to_ident(field_name), &mut idents);
+        generate_field(out, &ident, field_name, field);
+    }
+    out.push_str("    }\n\n");
+
+    let _ = writeln!(out, "    impl hass_rs::services::ServiceCall for {name} {{");
+    let _ = writeln!(
+        out,
+        "        fn domain(&self) -> &str {{\n            {domain:?}\n        }}\n"
+    );
+    let _ = writeln!(
+        out,
+        "        fn service(&self) -> &str {{\n            {service:?}\n        }}\n"
+    );
+    out.push_str("        fn target(&self) -> Option<&hass_rs::Target> {\n");
+    if hass_service.target.is_some() {
+        out.push_str("            Some(&self.target)\n");
+    } else {
+        out.push_str("            None\n");
+    }
+    out.push_str("        }\n\n");
+    out.push_str(
+        "        fn service_data(&self) -> hass_rs::HassResult<Option<serde_json::Value>> {\n",
+    );
+    out.push_str("            match serde_json::to_value(self)? {\n");
+    out.push_str("                serde_json::Value::Object(map) if map.is_empty() => Ok(None),\n");
+    out.push_str("                value => Ok(Some(value)),\n");
+    out.push_str("            }\n");
+    out.push_str("        }\n");
+    out.push_str("    }\n");
+}
+
+fn generate_field(out: &mut String, ident: &str, field_name: &str, field: &Field) {
+    let rust_type = field
+        .selector
+        .as_ref()
+        .map_or("serde_json::Value", selector_type);
+
+    out.push('\n');
+    if let Some(description) = &field.description {
+        write_doc(out, "        ", description);
+    }
+    if ident.trim_start_matches("r#") != field_name {
+        let _ = writeln!(out, "        #[serde(rename = {field_name:?})]");
+    }
+    if field.required {
+        let _ = writeln!(out, "        pub {ident}: {rust_type},");
+    } else {
+        out.push_str("        #[serde(skip_serializing_if = \"Option::is_none\")]\n");
+        let _ = writeln!(out, "        pub {ident}: Option<{rust_type}>,");
+    }
+}
+
+/// Infers the Rust type of a field from its selector, e.g. `{"number": {"min": 0, "max": 255}}`
+fn selector_type(selector: &Selector) -> &'static str {
+    match selector {
+        Selector::Boolean => "bool",
+        Selector::Number(_) => "f64",
+        Selector::ColorTemp(_) => "u32",
+        Selector::ColorRgb => "[u8; 3]",
+        selector if STRING_SELECTORS.contains(&selector.kind()) && selector.is_multiple() => {
+            "Vec<String>"
+        }
+        selector if STRING_SELECTORS.contains(&selector.kind()) => "String",
+        _ => "serde_json::Value",
+    }
+}
+
+/// the selectors whose value is a string, or a list of strings when `multiple` is set
+const STRING_SELECTORS: &[&str] = &[
+    "area",
+    "config_entry",
+    "conversation_agent",
+    "date",
+    "datetime",
+    "device",
+    "entity",
+    "floor",
+    "icon",
+    "label",
+    "language",
+    "select",
+    "template",
+    "text",
+    "theme",
+    "time",
+];
+
+fn write_doc(out: &mut String, indent: &str, text: &str) {
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            let _ = writeln!(out, "{indent}///");
+        } else {
+            let _ = writeln!(out, "{indent}/// {line}");
+        }
+    }
+}
+
+/// `turn_on` -> `TurnOn`
+fn to_type_name(name: &str) -> String {
+    let name: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Service{name}")
+    } else {
+        name
+    }
+}
+
+/// `name`, or `name` followed by the first free number if it is already used, e.g. `Svc22`
+fn unique_type_name(name: String, used: &mut HashSet<String>) -> String {
+    let name = match used.contains(&name) {
+        true => (2..)
+            .map(|n| format!("{name}{n}"))
+            .find(|name| !used.contains(name))
+            .expect("a free name"),
+        false => name,
+    };
+    used.insert(name.clone());
+    name
+}
+
+/// `ident`, or `ident` followed by as many `_` as needed if it is already used, e.g. `a_b_`
+fn unique_ident(mut ident: String, used: &mut HashSet<String>) -> String {
+    while used.contains(&ident) {
+        // the raw identifiers are keywords, these are not anymore once suffixed
+        ident = format!("{}_", ident.trim_start_matches("r#"));
+    }
+    used.insert(ident.clone());
+    ident
+}
+
+/// Makes a valid snake_case identifier, escaping the keywords
+fn to_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if ["crate", "self", "super"].contains(&ident.as_str()) {
+        // these can't be raw identifiers
+        ident.push('_');
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        ident.insert_str(0, "r#");
+    }
+    ident
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self",
+    "static", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized",
+    "use", "virtual", "where", "while", "yield",
+];
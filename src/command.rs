@@ -0,0 +1,119 @@
+//! Typed commands defined outside of this library
+//!
+//! A command implementing `HassCommand` is sent with `HassClient::send`, sharing the id
+//! allocation, the error mapping and the request timeout of the built-in commands.
+//!
+//! ```no_run
+//! use hass_rs::command::{HassCommand, OneShot};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize)]
+//! struct NodeStatusRequest {
+//!     device_id: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct NodeStatus {
+//!     node_id: u32,
+//!     status: u8,
+//!     ready: bool,
+//! }
+//!
+//! struct GetNodeStatus;
+//!
+//! impl HassCommand for GetNodeStatus {
+//!     const TYPE: &'static str = "zwave_js/node_status";
+//!     type Request = NodeStatusRequest;
+//!     type Response = NodeStatus;
+//!     type Kind = OneShot;
+//! }
+//!
+//! # async fn run(client: &mut hass_rs::HassClient) -> hass_rs::HassResult<()> {
+//! let request = NodeStatusRequest {
+//!     device_id: "8f4a21".to_owned(),
+//! };
+//! let status = client.send::<GetNodeStatus>(request).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::HaVersion;
+use crate::{HassClient, HassResult, HassSubscription};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::future::Future;
+
+/// A websocket command, identified by its `type`
+pub trait HassCommand {
+    /// The `type` of the command, e.g. `zwave_js/node_status`
+    const TYPE: &'static str;
+
+    /// The command fields sent next to `id` and `type`, it has to serialize to a JSON object,
+    /// or to `null` / `()` when the command has none
+    type Request: Serialize;
+
+    /// The result of a one-shot command, or the events of a subscription
+    type Response: DeserializeOwned + Send;
+
+    /// `OneShot` or `Subscription`
+    type Kind: CommandKind;
+
+    /// The first Home Assistant release knowing the command, `HassClient::send` fails with
+    /// `HassError::UnsupportedByServer` on an older server
+    const MIN_VERSION: Option<HaVersion> = None;
+}
+
+/// The command returns a single result
+#[derive(Debug)]
+pub struct OneShot;
+
+/// The command subscribes to events, returned as a `HassSubscription`
+#[derive(Debug)]
+pub struct Subscription;
+
+/// The marker telling how the response of a command is received, see `OneShot` and `Subscription`
+pub trait CommandKind: sealed::Sealed {
+    /// What `HassClient::send` returns for a `Response` of type `T`
+    type Output<T: DeserializeOwned + Send>;
+
+    #[doc(hidden)]
+    fn run<T: DeserializeOwned + Send>(
+        client: &mut HassClient,
+        msg_type: &'static str,
+        payload: Map<String, Value>,
+    ) -> impl Future<Output = HassResult<Self::Output<T>>> + Send;
+}
+
+impl CommandKind for OneShot {
+    type Output<T: DeserializeOwned + Send> = T;
+
+    async fn run<T: DeserializeOwned + Send>(
+        client: &mut HassClient,
+        msg_type: &'static str,
+        payload: Map<String, Value>,
+    ) -> HassResult<T> {
+        let value = client.send_object(msg_type, payload).await?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl CommandKind for Subscription {
+    type Output<T: DeserializeOwned + Send> = HassSubscription<T>;
+
+    async fn run<T: DeserializeOwned + Send>(
+        client: &mut HassClient,
+        msg_type: &'static str,
+        payload: Map<String, Value>,
+    ) -> HassResult<HassSubscription<T>> {
+        client.subscribe_object(msg_type, payload).await
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::OneShot {}
+    impl Sealed for super::Subscription {}
+}
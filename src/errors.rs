@@ -1,19 +1,25 @@
 //! Convenient error handling
 
+use crate::types::HaVersion;
 use crate::types::Response;
 use crate::types::WSResult;
+use crate::validation::ValidationError;
 use thiserror::Error;
-use tokio_tungstenite::tungstenite;
 
 pub type HassResult<T> = std::result::Result<T, HassError>;
 
 /// The error enum for Hass
+#[allow(clippy::large_enum_variant)]
 #[derive(Error, Debug)]
 pub enum HassError {
     /// Returned when it is unable to authenticate
     #[error("Authentication failed: {0}")]
     AuthenticationFailed(String),
 
+    /// Returned when a command is sent before the server accepted the access token
+    #[error("The connection is not authenticated")]
+    NotAuthenticated,
+
     /// Returned when serde was unable to deserialize the values
     #[error("Unable to deserialize received value: {0}")]
     UnableToDeserialize(#[from] serde_json::error::Error),
@@ -22,6 +28,10 @@ pub enum HassError {
     #[error("Connection closed unexpectedly")]
     ConnectionClosed,
 
+    /// Returned by the requests of a client once `close` was called, or pending when it was called
+    #[error("The client was closed")]
+    ClientClosed,
+
     /// Mpsc channel SendError<T> message
     #[error("Unable to send the message on channel: {0}")]
     SendError(String),
@@ -33,6 +43,20 @@ pub enum HassError {
     #[error("Tungstenite error: {0}")]
     TungsteniteError(#[from] tungstenite::error::Error),
 
+    /// Reqwest error
+    #[cfg(feature = "rest")]
+    #[error("Reqwest error: {0}")]
+    ReqwestError(#[from] reqwest::Error),
+
+    /// Returned when the REST API responds with an error status
+    #[cfg(feature = "rest")]
+    #[error("REST API error {status}: {message}")]
+    RestError { status: u16, message: String },
+
+    /// Returned when the response of a command is not received within the request timeout
+    #[error("No response received within {0:?}")]
+    Timeout(std::time::Duration),
+
     /// Returned when an unknown message format is received
     #[error("The received payload is unknown {0:?}")]
     UnknownPayloadReceived(Response),
@@ -45,7 +69,46 @@ pub enum HassError {
     #[error("ResponseError: {0:?}")]
     ResponseError(WSResult),
 
+    /// Returned when a command needs a newer Home Assistant than the one of the server
+    #[error("Requires Home Assistant {required}, the server runs {actual}")]
+    UnsupportedByServer {
+        required: HaVersion,
+        actual: HaVersion,
+    },
+
+    /// Returned when a Home Assistant version is not of the form `2024.6.0`
+    #[error("Invalid Home Assistant version: {0:?}")]
+    InvalidVersion(String),
+
+    /// Returned when a cron expression is not made of five valid fields
+    #[error("Invalid cron expression: {0:?}")]
+    InvalidCron(String),
+
+    /// Returned when an entity id is not of the form `domain.object_id`
+    #[error("Invalid entity id: {0:?}")]
+    InvalidEntityId(String),
+
+    /// Returned when a typed view is requested for an entity of another domain
+    #[error("Entity {entity_id} is not a {expected} entity")]
+    UnexpectedDomain { expected: String, entity_id: String },
+
+    /// Returned when a service call does not match the service schema
+    #[error("Invalid call of {domain}.{service}: {}", display_errors(.errors))]
+    InvalidServiceCall {
+        domain: String,
+        service: String,
+        errors: Vec<ValidationError>,
+    },
+
     /// Returned for errors which do not fit any of the above criteria
     #[error("Generic Error: {0}")]
     Generic(String),
 }
+
+fn display_errors(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,145 @@
+//! Data entry flow driver
+//!
+//! Home Assistant exposes the data entry flows (config, options and repair flows) only over its
+//! REST API, so the driver talks HTTP to the same server, authenticated with the long-lived
+//! access token used for the websocket.
+//! [Data Entry Flow](https://developers.home-assistant.io/docs/data_entry_flow_index)
+
+use crate::rest::error_for_status;
+use crate::types::{FlowStep, HassIssue};
+use crate::{HassError, HassResult};
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+/// The kind of data entry flow driven by FlowDriver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowKind {
+    /// Sets up a new config entry, the handler is the integration domain
+    Config,
+    /// Changes the options of a config entry, the handler is the config entry id
+    Options,
+    /// Fixes a repair issue, started with `FlowDriver::start_fix`
+    Repair,
+}
+
+impl FlowKind {
+    fn path(&self) -> &'static str {
+        match self {
+            Self::Config => "api/config/config_entries/flow",
+            Self::Options => "api/config/config_entries/options/flow",
+            Self::Repair => "api/repairs/issues/fix",
+        }
+    }
+}
+
+/// FlowDriver starts a data entry flow and walks it step by step
+///
+/// ```no_run
+/// # async fn run() -> hass_rs::HassResult<()> {
+/// use hass_rs::flow::{FlowDriver, FlowKind};
+/// use hass_rs::types::FlowStep;
+/// use serde_json::json;
+///
+/// let driver = FlowDriver::new("http://localhost:8123", "token", FlowKind::Config);
+/// let mut step = driver.start("met").await?;
+/// while let FlowStep::Form(form) = &step {
+///     step = driver.submit(&form.flow_id, json!({"name": "Home"})).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FlowDriver {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+    kind: FlowKind,
+}
+
+impl FlowDriver {
+    /// `base_url` is the Home Assistant server address, e.g. `http://localhost:8123`
+    pub fn new(base_url: &str, token: &str, kind: FlowKind) -> Self {
+        Self::with_http(reqwest::Client::new(), base_url, token, kind)
+    }
+
+    pub(crate) fn with_http(
+        http: reqwest::Client,
+        base_url: &str,
+        token: &str,
+        kind: FlowKind,
+    ) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            token: token.to_owned(),
+            kind,
+        }
+    }
+
+    /// Starts a new flow for the handler and returns its first step
+    ///
+    /// The repair flows are started with `start_fix` instead.
+    pub async fn start(&self, handler: &str) -> HassResult<FlowStep> {
+        if self.kind == FlowKind::Repair {
+            return Err(HassError::Generic(
+                "repair flows are started with FlowDriver::start_fix".to_owned(),
+            ));
+        }
+        let body = json!({
+            "handler": handler,
+            "show_advanced_options": false,
+        });
+        let request = self.http.post(self.url(None)).json(&body);
+        self.send(request).await
+    }
+
+    /// Starts the repair flow of a fixable issue and returns its first step
+    pub async fn start_fix(&self, issue: &HassIssue) -> HassResult<FlowStep> {
+        if self.kind != FlowKind::Repair {
+            return Err(HassError::Generic(
+                "repair flows require a FlowKind::Repair driver".to_owned(),
+            ));
+        }
+        let body = json!({
+            "handler": issue.domain,
+            "issue_id": issue.issue_id,
+        });
+        let request = self.http.post(self.url(None)).json(&body);
+        self.send(request).await
+    }
+
+    /// Returns the current step of a flow in progress
+    pub async fn progress(&self, flow_id: &str) -> HassResult<FlowStep> {
+        let request = self.http.get(self.url(Some(flow_id)));
+        self.send(request).await
+    }
+
+    /// Submits the user input of the current step and returns the next step
+    ///
+    /// For a menu step the input is `{"next_step_id": "<option>"}`.
+    pub async fn submit(&self, flow_id: &str, user_input: Value) -> HassResult<FlowStep> {
+        let request = self.http.post(self.url(Some(flow_id))).json(&user_input);
+        self.send(request).await
+    }
+
+    /// Aborts a flow in progress
+    pub async fn abort(&self, flow_id: &str) -> HassResult<()> {
+        let request = self.http.delete(self.url(Some(flow_id)));
+        let _: Value = self.send(request).await?;
+        Ok(())
+    }
+
+    fn url(&self, flow_id: Option<&str>) -> String {
+        match flow_id {
+            Some(flow_id) => format!("{}/{}/{}", self.base_url, self.kind.path(), flow_id),
+            None => format!("{}/{}", self.base_url, self.kind.path()),
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> HassResult<T> {
+        let response = request.bearer_auth(&self.token).send().await?;
+        let body = error_for_status(response).await?.text().await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+}
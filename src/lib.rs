@@ -13,3 +13,39 @@ pub use types::*;
 
 pub mod client;
 pub use client::HassClient;
+
+pub mod subscription;
+pub use subscription::{EventReceiver, HassSubscription};
+
+pub mod command;
+
+pub mod protocol;
+
+pub mod metrics;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+mod runtime;
+
+pub mod services;
+
+pub mod validation;
+
+#[cfg(feature = "rest")]
+pub mod flow;
+
+#[cfg(feature = "rest")]
+pub mod rest;
+
+#[cfg(feature = "rest")]
+pub mod publisher;
+
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+
+#[cfg(feature = "automation")]
+pub mod automation;
+
+#[cfg(feature = "codegen")]
+pub mod codegen;
@@ -0,0 +1,128 @@
+//! Health metrics of the websocket connection
+//!
+//! The client reports its requests, events and pings to a `HassMetrics` implementation set with
+//! `HassClient::with_metrics`. All the methods do nothing by default, so an implementation only
+//! overrides the ones it graphs. With the `metrics` feature, `MetricsRecorder` forwards them to
+//! the `metrics` crate facade, and from there to any exporter, e.g. Prometheus or OpenTelemetry.
+//!
+//! With the `tracing` feature, each command also runs in a `hass.command` span recording its
+//! `type`, `id`, `latency_ms` and `outcome`.
+
+use std::time::Duration;
+
+/// How a request ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestOutcome {
+    /// The server answered with a successful result
+    Success,
+    /// The server answered with an error result
+    Error,
+    /// No response was received within the request timeout
+    Timeout,
+    /// The connection was closed before the response was received
+    Closed,
+    /// The request was dropped before its response was received, e.g. by a timeout of the
+    /// caller or an aborted task
+    Cancelled,
+    /// The request could not be sent, or its response could not be received
+    Failed,
+}
+
+impl RequestOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Error => "error",
+            Self::Timeout => "timeout",
+            Self::Closed => "closed",
+            Self::Cancelled => "cancelled",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Receives the metrics of a client, see the module documentation
+///
+/// The methods are called from the tasks of the connection, they should not block.
+#[allow(unused_variables)]
+pub trait HassMetrics: Send + Sync {
+    /// A connection was opened, counting the reconnections of an application creating a new
+    /// client once the previous connection is gone
+    fn connection_opened(&self) {}
+
+    /// A request was sent, the requests in flight are the started and not yet finished ones
+    fn request_started(&self, msg_type: &str) {}
+
+    /// A request got its response, failed or was cancelled, each started request finishes once
+    fn request_finished(&self, msg_type: &str, latency: Duration, outcome: RequestOutcome) {}
+
+    /// An event was received for a subscription, `msg_type` is the type of the command which
+    /// subscribed, e.g. `subscribe_events`
+    fn event_received(&self, msg_type: &str) {}
+
+    /// An event was dropped, its receiver was dropped or its subscription is gone, in which
+    /// case `msg_type` is `unknown`
+    fn event_dropped(&self, msg_type: &str) {}
+
+    /// A message or an event could not be deserialized
+    fn deserialization_failed(&self) {}
+
+    /// The round trip time of a `ping`
+    fn ping_rtt(&self, rtt: Duration) {}
+}
+
+/// The `HassMetrics` reporting through the `metrics` crate facade
+///
+/// | metric | kind | labels |
+/// |---|---|---|
+/// | `hass_connections_total` | counter | |
+/// | `hass_requests_in_flight` | gauge | |
+/// | `hass_requests_total` | counter | `type`, `outcome` |
+/// | `hass_request_duration_seconds` | histogram | `type`, `outcome` |
+/// | `hass_events_received_total` | counter | `type` |
+/// | `hass_events_dropped_total` | counter | `type` |
+/// | `hass_deserialization_failures_total` | counter | |
+/// | `hass_ping_rtt_seconds` | histogram | |
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MetricsRecorder;
+
+#[cfg(feature = "metrics")]
+impl HassMetrics for MetricsRecorder {
+    fn connection_opened(&self) {
+        ::metrics::counter!("hass_connections_total").increment(1);
+    }
+
+    fn request_started(&self, _msg_type: &str) {
+        ::metrics::gauge!("hass_requests_in_flight").increment(1);
+    }
+
+    fn request_finished(&self, msg_type: &str, latency: Duration, outcome: RequestOutcome) {
+        ::metrics::gauge!("hass_requests_in_flight").decrement(1);
+
+        let labels = [
+            ("type", msg_type.to_owned()),
+            ("outcome", outcome.as_str().to_owned()),
+        ];
+        ::metrics::counter!("hass_requests_total", &labels).increment(1);
+        ::metrics::histogram!("hass_request_duration_seconds", &labels).record(latency);
+    }
+
+    fn event_received(&self, msg_type: &str) {
+        ::metrics::counter!("hass_events_received_total", "type" => msg_type.to_owned())
+            .increment(1);
+    }
+
+    fn event_dropped(&self, msg_type: &str) {
+        ::metrics::counter!("hass_events_dropped_total", "type" => msg_type.to_owned())
+            .increment(1);
+    }
+
+    fn deserialization_failed(&self) {
+        ::metrics::counter!("hass_deserialization_failures_total").increment(1);
+    }
+
+    fn ping_rtt(&self, rtt: Duration) {
+        ::metrics::histogram!("hass_ping_rtt_seconds").record(rtt);
+    }
+}
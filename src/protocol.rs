@@ -0,0 +1,318 @@
+//! Sans-IO engine of the Home Assistant websocket protocol
+//!
+//! `Protocol` owns the message ids, the pending requests, the subscriptions and the
+//! authentication state, but neither a socket nor a task: it turns the requests into text
+//! frames to be sent, and the received text frames into `Outcome`s. `HassClient` drives it
+//! over tokio-tungstenite or async-tungstenite, a custom transport can drive it the same way.
+//!
+//! ```
+//! use hass_rs::protocol::{ConnectionState, Outcome, Protocol};
+//! use serde_json::Map;
+//!
+//! # fn main() -> hass_rs::HassResult<()> {
+//! let mut protocol = Protocol::new();
+//! protocol.receive(r#"{"type":"auth_required","ha_version":"2024.6.0"}"#)?;
+//!
+//! let _frame = protocol.auth("my_token")?;
+//! protocol.receive(r#"{"type":"auth_ok","ha_version":"2024.6.0"}"#)?;
+//! assert_eq!(protocol.state(), ConnectionState::Authenticated);
+//!
+//! let (id, _frame) = protocol.command("get_config", Map::new())?;
+//! let outcomes = protocol.receive(r#"{"id":1,"type":"result","success":true,"result":{}}"#)?;
+//! assert!(matches!(outcomes[0], Outcome::Response { id: Some(1), .. }));
+//! # assert_eq!(id, 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::{Auth, Command, RawCommand, Response, Unsubscribe};
+use crate::{HassError, HassResult};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// The stage of the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Waiting for the `auth_required` message of the server
+    Connecting,
+    /// The server asked for the access token
+    AuthRequired,
+    /// The access token was sent
+    Authenticating,
+    /// The server accepted the access token, commands can be sent
+    Authenticated,
+    /// The server rejected the access token, it closes the connection
+    AuthFailed,
+    /// The connection is gone, no more commands can be sent
+    Closed,
+}
+
+/// What a received frame, or the close of the connection, means for the caller
+#[derive(Debug)]
+pub enum Outcome {
+    /// The server asked for authentication, see `Protocol::auth`
+    AuthRequired { ha_version: String },
+    /// The response to a pending request, `id` is `None` for the response to the authentication
+    Response { id: Option<u64>, response: Response },
+    /// The payload of an event of an active subscription
+    Event { subscription: u64, event: Value },
+    /// The payload of an event of a subscription which is not active, e.g. one received right
+    /// after unsubscribing
+    UnmatchedEvent { subscription: u64, event: Value },
+    /// A message matching no pending request nor active subscription, e.g. a second result
+    /// with the same id
+    Unmatched(Response),
+    /// A message of an array frame which could not be decoded, the other messages of the frame
+    /// are returned as usual
+    Invalid { message: Value, error: HassError },
+}
+
+/// The state machine of a websocket connection, see the module documentation
+#[derive(Debug)]
+pub struct Protocol {
+    last_id: u64,
+    state: ConnectionState,
+    pending: HashSet<u64>,
+    // requests whose result is dropped without an outcome, e.g. after a timeout
+    cancelled: HashSet<u64>,
+    // the subscriptions and the types of the commands which subscribed them
+    subscriptions: HashMap<u64, String>,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[allow(clippy::result_large_err)]
+impl Protocol {
+    pub fn new() -> Self {
+        Self {
+            last_id: 0,
+            state: ConnectionState::Connecting,
+            pending: HashSet::new(),
+            cancelled: HashSet::new(),
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Whether a request is waiting for its response
+    pub fn is_pending(&self, id: u64) -> bool {
+        self.pending.contains(&id)
+    }
+
+    /// Whether the events of a subscription are routed to `Outcome::Event`
+    pub fn is_subscribed(&self, subscription: u64) -> bool {
+        self.subscriptions.contains_key(&subscription)
+    }
+
+    /// The type of the command of an active subscription, e.g. `subscribe_events`
+    pub fn subscription_type(&self, subscription: u64) -> Option<&str> {
+        self.subscriptions.get(&subscription).map(String::as_str)
+    }
+
+    /// Returns the frame authenticating the session with a long-lived access token
+    ///
+    /// The response is an `Outcome::Response` without id, holding `AuthOk` or `AuthInvalid`.
+    pub fn auth(&mut self, access_token: &str) -> HassResult<String> {
+        let cmd = Command::AuthInit(Auth {
+            msg_type: "auth".to_owned(),
+            access_token: access_token.to_owned(),
+        });
+        self.send_command(&cmd, None)
+    }
+
+    /// Returns the id and the frame of a command, `payload` holds the fields next to `type`
+    pub fn command(
+        &mut self,
+        msg_type: &str,
+        payload: Map<String, Value>,
+    ) -> HassResult<(u64, String)> {
+        // a refused command does not take an id
+        self.check_authenticated()?;
+        let id = self.next_id();
+        let cmd = Command::Raw(RawCommand {
+            id,
+            msg_type: msg_type.to_owned(),
+            payload,
+        });
+        Ok((id, self.send_command(&cmd, Some(id))?))
+    }
+
+    /// Same as `command`, the events sent with the returned id are routed to `Outcome::Event`
+    ///
+    /// The subscription is dropped if the server answers with an error.
+    pub fn subscribe(
+        &mut self,
+        msg_type: &str,
+        payload: Map<String, Value>,
+    ) -> HassResult<(u64, String)> {
+        let (id, frame) = self.command(msg_type, payload)?;
+        self.subscriptions.insert(id, msg_type.to_owned());
+        Ok((id, frame))
+    }
+
+    /// Returns the id and the frame of the `unsubscribe_events` command of a subscription,
+    /// its events are not routed anymore
+    pub fn unsubscribe(&mut self, subscription: u64) -> HassResult<(u64, String)> {
+        self.check_authenticated()?;
+        let id = self.next_id();
+        let cmd = Command::Unsubscribe(Unsubscribe {
+            id,
+            msg_type: "unsubscribe_events".to_owned(),
+            subscription,
+        });
+        let frame = self.send_command(&cmd, Some(id))?;
+        self.subscriptions.remove(&subscription);
+        Ok((id, frame))
+    }
+
+    /// Stops waiting for the response of a request, it is dropped when it arrives
+    pub fn cancel(&mut self, id: u64) {
+        if self.pending.remove(&id) {
+            self.cancelled.insert(id);
+        }
+    }
+
+    /// Decodes a received text frame, either a single message or an array of messages
+    ///
+    /// A single message which cannot be decoded is an error, while the messages of an array
+    /// which cannot be decoded are returned as `Outcome::Invalid`.
+    pub fn receive(&mut self, text: &str) -> HassResult<Vec<Outcome>> {
+        match serde_json::from_str(text)? {
+            Value::Array(messages) => {
+                let mut outcomes = Vec::with_capacity(messages.len());
+                for mut message in messages {
+                    match self.receive_message(&mut message) {
+                        Ok(outcome) => outcomes.extend(outcome),
+                        Err(error) => outcomes.push(Outcome::Invalid { message, error }),
+                    }
+                }
+                Ok(outcomes)
+            }
+            mut message => Ok(self.receive_message(&mut message)?.into_iter().collect()),
+        }
+    }
+
+    /// The connection is gone, every pending request gets a `Response::Close`
+    pub fn close(&mut self, reason: &str) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        if self.state == ConnectionState::Authenticating {
+            outcomes.push(Outcome::Response {
+                id: None,
+                response: Response::Close(reason.to_owned()),
+            });
+        }
+
+        let mut pending: Vec<u64> = self.pending.drain().collect();
+        pending.sort_unstable();
+        outcomes.extend(pending.into_iter().map(|id| Outcome::Response {
+            id: Some(id),
+            response: Response::Close(reason.to_owned()),
+        }));
+
+        self.cancelled.clear();
+        self.subscriptions.clear();
+        self.state = ConnectionState::Closed;
+        outcomes
+    }
+
+    /// Allocates the id of a command built by the client
+    pub(crate) fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    /// Returns the frame of a command built by the client, waiting for its response
+    ///
+    /// The commands with an id are refused until the server accepted the access token.
+    pub(crate) fn send_command(&mut self, cmd: &Command, id: Option<u64>) -> HassResult<String> {
+        match id {
+            Some(id) => {
+                self.check_authenticated()?;
+                self.pending.insert(id);
+            }
+            None if self.state == ConnectionState::Closed => {
+                return Err(HassError::ConnectionClosed)
+            }
+            None => self.state = ConnectionState::Authenticating,
+        }
+        Ok(serde_json::to_string(cmd)?)
+    }
+
+    fn check_authenticated(&self) -> HassResult<()> {
+        match self.state {
+            ConnectionState::Authenticated => Ok(()),
+            ConnectionState::Closed => Err(HassError::ConnectionClosed),
+            _ => Err(HassError::NotAuthenticated),
+        }
+    }
+
+    /// Routes the events sent with the id of a command sent by the client
+    pub(crate) fn track_subscription(&mut self, id: u64, msg_type: &str) {
+        self.subscriptions.insert(id, msg_type.to_owned());
+    }
+
+    /// Stops routing the events of a subscription
+    pub(crate) fn forget_subscription(&mut self, subscription: u64) {
+        self.subscriptions.remove(&subscription);
+    }
+
+    fn receive_message(&mut self, message: &mut Value) -> HassResult<Option<Outcome>> {
+        if message["type"] == "event" {
+            if let Some(id) = message["id"].as_u64() {
+                let event = message["event"].take();
+                return Ok(Some(if self.is_subscribed(id) {
+                    Outcome::Event {
+                        subscription: id,
+                        event,
+                    }
+                } else {
+                    Outcome::UnmatchedEvent {
+                        subscription: id,
+                        event,
+                    }
+                }));
+            }
+        }
+
+        let response = Response::deserialize(&*message)?;
+        let outcome = match response {
+            Response::AuthRequired(required) => {
+                self.state = ConnectionState::AuthRequired;
+                Outcome::AuthRequired {
+                    ha_version: required.ha_version,
+                }
+            }
+            response @ Response::AuthOk(_) => {
+                self.state = ConnectionState::Authenticated;
+                Outcome::Response { id: None, response }
+            }
+            response @ Response::AuthInvalid(_) => {
+                self.state = ConnectionState::AuthFailed;
+                Outcome::Response { id: None, response }
+            }
+            response => match response.id() {
+                Some(id) if self.pending.remove(&id) => {
+                    if matches!(&response, Response::Result(result) if result.is_err()) {
+                        self.subscriptions.remove(&id);
+                    }
+                    Outcome::Response {
+                        id: Some(id),
+                        response,
+                    }
+                }
+                Some(id) if self.cancelled.remove(&id) => return Ok(None),
+                _ => Outcome::Unmatched(response),
+            },
+        };
+        Ok(Some(outcome))
+    }
+}
@@ -0,0 +1,278 @@
+//! Virtual entities published by the application
+//!
+//! `StatePublisher` creates and updates entities which exist only in the state machine of
+//! Home Assistant, e.g. the computed values of a daemon, through the REST API. It only pushes
+//! the states which changed, at most once per `min_interval` per entity, the last state set
+//! in between being pushed once the interval elapsed. The states are lost when Home Assistant
+//! restarts, `republish_on_restart` pushes them again once it is running again.
+//!
+//! ```no_run
+//! # async fn run() -> hass_rs::HassResult<()> {
+//! use hass_rs::publisher::{StatePublisher, VirtualEntity};
+//! use hass_rs::rest::HassRestClient;
+//! use hass_rs::{EntityId, HassClient};
+//! use std::time::Duration;
+//!
+//! let rest = HassRestClient::new("http://localhost:8123", "token");
+//! let publisher = StatePublisher::new(rest, Duration::from_secs(10));
+//!
+//! let mut client = HassClient::new("ws://localhost:8123/api/websocket").await?;
+//! client.auth_with_longlivedtoken("token").await?;
+//! publisher.republish_on_restart(&mut client).await?;
+//!
+//! let mut occupancy = VirtualEntity::new(EntityId::new("sensor.office_occupancy")?, "0.8");
+//! occupancy.unit_of_measurement = Some("%".to_owned());
+//! occupancy.friendly_name = Some("Office occupancy".to_owned());
+//! publisher.publish(&occupancy).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::rest::HassRestClient;
+use crate::types::{CoreState, EntityId};
+use crate::{runtime, HassClient, HassResult};
+
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+/// how often Home Assistant is polled while it restarts
+const RESTART_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The state and attributes of an entity published by the application
+#[derive(Debug, PartialEq, Clone)]
+pub struct VirtualEntity {
+    pub entity_id: EntityId,
+    pub state: String,
+    pub unit_of_measurement: Option<String>,
+    /// e.g. `temperature`, see the device classes of the domain of the entity
+    pub device_class: Option<String>,
+    pub friendly_name: Option<String>,
+    /// e.g. `mdi:account`
+    pub icon: Option<String>,
+    /// the other attributes
+    pub attributes: Map<String, Value>,
+}
+
+impl VirtualEntity {
+    pub fn new(entity_id: EntityId, state: impl Into<String>) -> Self {
+        Self {
+            entity_id,
+            state: state.into(),
+            unit_of_measurement: None,
+            device_class: None,
+            friendly_name: None,
+            icon: None,
+            attributes: Map::new(),
+        }
+    }
+
+    /// the attributes sent to Home Assistant
+    fn all_attributes(&self) -> Value {
+        let mut attributes = self.attributes.clone();
+        let known = [
+            ("unit_of_measurement", &self.unit_of_measurement),
+            ("device_class", &self.device_class),
+            ("friendly_name", &self.friendly_name),
+            ("icon", &self.icon),
+        ];
+        for (name, value) in known {
+            if let Some(value) = value {
+                attributes.insert(name.to_owned(), Value::String(value.clone()));
+            }
+        }
+        Value::Object(attributes)
+    }
+}
+
+/// Publishes virtual entities, see the module documentation
+///
+/// The clones share the published states.
+#[derive(Debug, Clone)]
+pub struct StatePublisher {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    rest: HassRestClient,
+    min_interval: Duration,
+    entities: Mutex<HashMap<EntityId, Published>>,
+}
+
+/// a state and its attributes
+type Payload = (String, Value);
+
+#[derive(Debug, Default)]
+struct Published {
+    // the last state pushed successfully
+    pushed: Option<Payload>,
+    pushed_at: Option<Instant>,
+    // the last state set within the interval, pushed once it elapsed
+    pending: Option<Payload>,
+}
+
+impl StatePublisher {
+    /// `min_interval` is the minimum time between two updates of an entity, `Duration::ZERO`
+    /// to push every change
+    pub fn new(rest: HassRestClient, min_interval: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                rest,
+                min_interval,
+                entities: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Creates or updates the entity, returns whether its state was pushed right away
+    ///
+    /// Nothing is pushed if the state and attributes did not change. Within `min_interval` of
+    /// the previous update the state is pushed in the background once the interval elapsed,
+    /// unless another state is set in between.
+    pub async fn publish(&self, entity: &VirtualEntity) -> HassResult<bool> {
+        let payload = (entity.state.clone(), entity.all_attributes());
+
+        {
+            let mut entities = self.inner.entities.lock();
+            let published = entities.entry(entity.entity_id.clone()).or_default();
+
+            if published.pushed.as_ref() == Some(&payload) {
+                // back to the state Home Assistant shows, a pending one is outdated
+                published.pending = None;
+                return Ok(false);
+            }
+
+            let elapsed = published.pushed_at.map(|pushed_at| pushed_at.elapsed());
+            match elapsed {
+                Some(elapsed) if elapsed < self.inner.min_interval => {
+                    // a flush is already scheduled if a state is pending
+                    if published.pending.replace(payload).is_none() {
+                        let publisher = self.clone();
+                        let entity_id = entity.entity_id.clone();
+                        let wait = self.inner.min_interval - elapsed;
+                        runtime::spawn(async move {
+                            runtime::sleep(wait).await;
+                            publisher.flush(entity_id).await;
+                        });
+                    }
+                    return Ok(false);
+                }
+                _ => {
+                    published.pending = None;
+                    published.pushed_at = Some(Instant::now());
+                }
+            }
+        }
+
+        self.push(&entity.entity_id, payload).await?;
+        Ok(true)
+    }
+
+    /// Pushes the states of all the published entities again, e.g. after a restart of
+    /// Home Assistant, returns the last error
+    pub async fn republish_all(&self) -> HassResult<()> {
+        let payloads: Vec<(EntityId, Payload)> = {
+            let mut entities = self.inner.entities.lock();
+            entities
+                .iter_mut()
+                .filter_map(|(entity_id, published)| {
+                    let payload = published.pending.take().or(published.pushed.clone())?;
+                    published.pushed_at = Some(Instant::now());
+                    Some((entity_id.clone(), payload))
+                })
+                .collect()
+        };
+
+        let mut result = Ok(());
+        for (entity_id, payload) in payloads {
+            if let Err(err) = self.push(&entity_id, payload).await {
+                log::warn!("unable to republish {entity_id}: {err}");
+                result = Err(err);
+            }
+        }
+        result
+    }
+
+    /// Republishes all the entities when Home Assistant has restarted
+    ///
+    /// A restart closes the websocket connection of the client, which does not reconnect. Once
+    /// the connection is lost, Home Assistant is polled through the REST API until it is
+    /// running again, then the entities are republished. The entities are also republished on
+    /// the `homeassistant_started` events of the connection, so that a client connected while
+    /// Home Assistant is starting republishes them once it is started.
+    ///
+    /// The watch ends once the entities are republished after the connection is lost, or when
+    /// all the clones of the publisher are dropped: to follow the next restarts, call it again
+    /// with a new client.
+    pub async fn republish_on_restart(&self, client: &mut HassClient) -> HassResult<()> {
+        let mut events = client.subscribe_event("homeassistant_started").await?;
+        let inner = Arc::downgrade(&self.inner);
+        runtime::spawn(async move {
+            while events.recv().await.is_some() {
+                let Some(publisher) = Self::upgrade(&inner) else {
+                    return;
+                };
+                log::debug!("Home Assistant started, republishing the virtual entities");
+                publisher.republish_all().await.ok();
+            }
+
+            log::debug!("connection lost, waiting for Home Assistant to run again");
+            loop {
+                let Some(publisher) = Self::upgrade(&inner) else {
+                    return;
+                };
+                let state = publisher.inner.rest.get_config().await.map(|c| c.state);
+                if let Ok(Some(CoreState::Running)) = state {
+                    if publisher.republish_all().await.is_ok() {
+                        return;
+                    }
+                }
+                drop(publisher);
+                runtime::sleep(RESTART_POLL_INTERVAL).await;
+            }
+        });
+        Ok(())
+    }
+
+    fn upgrade(inner: &Weak<Inner>) -> Option<Self> {
+        inner.upgrade().map(|inner| Self { inner })
+    }
+
+    /// pushes the pending state of an entity, once its interval elapsed
+    async fn flush(&self, entity_id: EntityId) {
+        let payload = {
+            let mut entities = self.inner.entities.lock();
+            let Some(published) = entities.get_mut(&entity_id) else {
+                return;
+            };
+            let Some(payload) = published.pending.take() else {
+                return;
+            };
+            published.pushed_at = Some(Instant::now());
+            payload
+        };
+
+        if let Err(err) = self.push(&entity_id, payload).await {
+            log::warn!("unable to publish {entity_id}: {err}");
+        }
+    }
+
+    async fn push(&self, entity_id: &EntityId, payload: Payload) -> HassResult<()> {
+        let (state, attributes) = payload.clone();
+        let result = self
+            .inner
+            .rest
+            .set_state(entity_id.as_str(), &state, attributes)
+            .await;
+
+        // a state which failed to be pushed is pushed again by the next publish
+        let pushed = result.is_ok().then_some(payload);
+        if let Some(published) = self.inner.entities.lock().get_mut(entity_id) {
+            published.pushed = pushed;
+        }
+        result.map(|_| ())
+    }
+}
@@ -0,0 +1,257 @@
+//! REST API companion client
+//!
+//! A few features of Home Assistant are only exposed over its REST API: the camera snapshots,
+//! the template rendering, the states set without an integration, the calendars, the error log,
+//! the configuration check and the deletion of the config entries. `HassRestClient` talks HTTP
+//! to the same server as the websocket client, authenticated with the same long-lived access
+//! token, and decodes the responses into the types of the crate.
+//! reqwest runs on tokio, so the `rest` feature needs the `tokio-runtime` feature.
+//! [REST API](https://developers.home-assistant.io/docs/api/rest)
+
+#[cfg(not(feature = "tokio-runtime"))]
+compile_error!("the rest feature needs the tokio-runtime feature, reqwest runs on tokio");
+
+use crate::flow::{FlowDriver, FlowKind};
+use crate::types::{
+    CalendarEvent, ConfigCheck, HassCalendar, HassConfig, HassEntity, HassServices, ServiceName,
+};
+use crate::{HassError, HassResult};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// HassRestClient sends the requests of the REST API
+///
+/// ```no_run
+/// # async fn run() -> hass_rs::HassResult<()> {
+/// use hass_rs::rest::HassRestClient;
+/// use serde_json::json;
+///
+/// let client = HassRestClient::new("http://localhost:8123", "token");
+/// let greeting = client.render_template("Hello {{ states('sun.sun') }}").await?;
+///
+/// client
+///     .set_state("sensor.backup", "done", json!({"friendly_name": "Backup"}))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HassRestClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl HassRestClient {
+    /// `base_url` is the Home Assistant server address, e.g. `http://localhost:8123`
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            token: token.to_owned(),
+        }
+    }
+
+    /// A driver of the data entry flows sharing the connection pool and token of this client
+    pub fn flow(&self, kind: FlowKind) -> FlowDriver {
+        FlowDriver::with_http(self.http.clone(), &self.base_url, &self.token, kind)
+    }
+
+    /// This will get the current config of the Home Assistant.
+    pub async fn get_config(&self) -> HassResult<HassConfig> {
+        self.get_json("api/config").await
+    }
+
+    /// This will get the states of all the entities.
+    pub async fn get_states(&self) -> HassResult<Vec<HassEntity>> {
+        self.get_json("api/states").await
+    }
+
+    /// This will get the state of an entity.
+    pub async fn get_state(&self, entity_id: &str) -> HassResult<HassEntity> {
+        self.get_json(&format!("api/states/{entity_id}")).await
+    }
+
+    /// This will set the state of an entity, creating it if needed, and return the new state.
+    ///
+    /// The state only lives in Home Assistant, no device is updated and the state is replaced
+    /// by the integration of the entity, if any, on its next update.
+    pub async fn set_state(
+        &self,
+        entity_id: &str,
+        state: &str,
+        attributes: Value,
+    ) -> HassResult<HassEntity> {
+        let body = json!({
+            "state": state,
+            "attributes": attributes,
+        });
+        let request = self
+            .http
+            .post(self.url(&format!("api/states/{entity_id}")))
+            .json(&body);
+        self.send_json(request).await
+    }
+
+    /// This will get all the services by domain, the same as `HassClient::get_services`.
+    pub async fn get_services(&self) -> HassResult<HassServices> {
+        // the REST API lists the domains instead of mapping them
+        #[derive(Deserialize)]
+        struct Domain {
+            domain: String,
+            services: ServiceName,
+        }
+
+        let domains: Vec<Domain> = self.get_json("api/services").await?;
+        Ok(HassServices(
+            domains
+                .into_iter()
+                .map(|domain| (domain.domain, domain.services))
+                .collect(),
+        ))
+    }
+
+    /// This will fire an event and return the confirmation message of the server.
+    pub async fn fire_event(&self, event_type: &str, event_data: Value) -> HassResult<String> {
+        let request = self
+            .http
+            .post(self.url(&format!("api/events/{event_type}")))
+            .json(&event_data);
+        let response: Value = self.send_json(request).await?;
+        Ok(response["message"].as_str().unwrap_or_default().to_owned())
+    }
+
+    /// This will render a template with the current states.
+    pub async fn render_template(&self, template: &str) -> HassResult<String> {
+        let request = self
+            .http
+            .post(self.url("api/template"))
+            .json(&json!({ "template": template }));
+        self.send_text(request).await
+    }
+
+    /// This will get the state changes of the entities since `start_time`, one list per entity.
+    ///
+    /// The times are ISO 8601 timestamps, `end_time` defaults to one day after `start_time`.
+    /// Home Assistant requires at least one entity id, an empty `entity_ids` is an error.
+    pub async fn get_history(
+        &self,
+        start_time: &str,
+        end_time: Option<&str>,
+        entity_ids: &[&str],
+    ) -> HassResult<Vec<Vec<HassEntity>>> {
+        if entity_ids.is_empty() {
+            return Err(HassError::Generic(
+                "the history needs at least one entity id".to_owned(),
+            ));
+        }
+        let mut query = vec![("filter_entity_id", entity_ids.join(","))];
+        if let Some(end_time) = end_time {
+            query.push(("end_time", end_time.to_owned()));
+        }
+
+        let request = self
+            .http
+            .get(self.url(&format!("api/history/period/{start_time}")))
+            .query(&query);
+        self.send_json(request).await
+    }
+
+    /// This will get the current snapshot of a camera, in the image format of the camera.
+    pub async fn camera_proxy(&self, entity_id: &str) -> HassResult<Vec<u8>> {
+        let request = self
+            .http
+            .get(self.url(&format!("api/camera_proxy/{entity_id}")));
+        let response = self.send(request).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// This will list the calendar entities.
+    pub async fn get_calendars(&self) -> HassResult<Vec<HassCalendar>> {
+        self.get_json("api/calendars").await
+    }
+
+    /// This will get the events of a calendar between two ISO 8601 timestamps.
+    pub async fn get_calendar_events(
+        &self,
+        entity_id: &str,
+        start: &str,
+        end: &str,
+    ) -> HassResult<Vec<CalendarEvent>> {
+        let request = self
+            .http
+            .get(self.url(&format!("api/calendars/{entity_id}")))
+            .query(&[("start", start), ("end", end)]);
+        self.send_json(request).await
+    }
+
+    /// This will get the error log of the current session of Home Assistant.
+    pub async fn get_error_log(&self) -> HassResult<String> {
+        let request = self.http.get(self.url("api/error_log"));
+        self.send_text(request).await
+    }
+
+    /// This will check the configuration files, e.g. before a restart.
+    pub async fn check_config(&self) -> HassResult<ConfigCheck> {
+        let request = self.http.post(self.url("api/config/core/check_config"));
+        self.send_json(request).await
+    }
+
+    /// This will delete a config entry, returns whether Home Assistant has to be restarted for
+    /// the change to take effect.
+    pub async fn delete_config_entry(&self, entry_id: &str) -> HassResult<bool> {
+        let request = self
+            .http
+            .delete(self.url(&format!("api/config/config_entries/entry/{entry_id}")));
+        let result: Value = self.send_json(request).await?;
+        Ok(result["require_restart"].as_bool().unwrap_or(false))
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> HassResult<T> {
+        let request = self.http.get(self.url(path));
+        self.send_json(request).await
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> HassResult<T> {
+        let body = self.send_text(request).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    async fn send_text(&self, request: reqwest::RequestBuilder) -> HassResult<String> {
+        let response = self.send(request).await?;
+        Ok(response.text().await?)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> HassResult<reqwest::Response> {
+        let response = request.bearer_auth(&self.token).send().await?;
+        error_for_status(response).await
+    }
+}
+
+/// fails with `HassError::RestError` if the server answered with an error status
+pub(crate) async fn error_for_status(response: reqwest::Response) -> HassResult<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    // Home Assistant answers with {"message": "..."} on errors
+    let body = response.text().await?;
+    let message = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|value| value["message"].as_str().map(str::to_owned))
+        .unwrap_or(body);
+    Err(HassError::RestError {
+        status: status.as_u16(),
+        message,
+    })
+}
@@ -0,0 +1,139 @@
+//! The executor dependent parts of the client, selected with the `*-runtime` features
+//!
+//! The client only needs to connect the websocket, to spawn the tasks reading and writing it,
+//! and to sleep for the request timeout. Everything else, the channels included, is executor
+//! agnostic.
+
+use crate::HassResult;
+
+use futures_util::future::{select, Either};
+use futures_util::{Sink, Stream, StreamExt};
+use std::future::Future;
+use std::time::Duration;
+use tungstenite::{Error, Message};
+
+#[cfg(not(any(
+    feature = "tokio-runtime",
+    feature = "async-std-runtime",
+    feature = "smol-runtime"
+)))]
+compile_error!("enable one of the tokio-runtime, async-std-runtime or smol-runtime features");
+
+/// Connects the websocket, returning its write and read halves
+pub(crate) async fn connect(
+    url: &str,
+) -> HassResult<(
+    impl Sink<Message, Error = Error> + Send + Unpin + 'static,
+    impl Stream<Item = Result<Message, Error>> + Send + Unpin + 'static,
+)> {
+    #[cfg(feature = "tokio-runtime")]
+    let (wsclient, _) = tokio_tungstenite::connect_async(url).await?;
+
+    // async-std is deprecated upstream, it is still supported for the existing users
+    #[cfg(all(feature = "async-std-runtime", not(feature = "tokio-runtime")))]
+    #[allow(deprecated)]
+    let (wsclient, _) = async_tungstenite::async_std::connect_async(url).await?;
+
+    #[cfg(all(
+        feature = "smol-runtime",
+        not(any(feature = "tokio-runtime", feature = "async-std-runtime"))
+    ))]
+    let (wsclient, _) = async_tungstenite::smol::connect_async(url).await?;
+
+    Ok(StreamExt::split(wsclient))
+}
+
+/// Runs the task in the background
+pub(crate) fn spawn<F>(task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "tokio-runtime")]
+    tokio::spawn(task);
+
+    #[cfg(all(feature = "async-std-runtime", not(feature = "tokio-runtime")))]
+    async_std::task::spawn(task);
+
+    #[cfg(all(
+        feature = "smol-runtime",
+        not(any(feature = "tokio-runtime", feature = "async-std-runtime"))
+    ))]
+    smol::spawn(task).detach();
+}
+
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "tokio-runtime")]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(all(feature = "async-std-runtime", not(feature = "tokio-runtime")))]
+    async_std::task::sleep(duration).await;
+
+    #[cfg(all(
+        feature = "smol-runtime",
+        not(any(feature = "tokio-runtime", feature = "async-std-runtime"))
+    ))]
+    smol::Timer::after(duration).await;
+}
+
+/// Runs the future, returns `None` if it is not done within `duration`
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    let future = std::pin::pin!(future);
+    let sleep = std::pin::pin!(sleep(duration));
+    match select(future, sleep).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+/// The executor of the blocking client, a tokio runtime with a single worker thread,
+/// or the global executor of async-std or smol
+#[cfg(feature = "blocking")]
+pub(crate) struct BlockingRuntime {
+    #[cfg(feature = "tokio-runtime")]
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingRuntime {
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn new() -> HassResult<Self> {
+        #[cfg(feature = "tokio-runtime")]
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|err| crate::HassError::Generic(err.to_string()))?;
+
+        Ok(Self {
+            #[cfg(feature = "tokio-runtime")]
+            runtime,
+        })
+    }
+
+    /// Runs the future to completion on the calling thread
+    pub(crate) fn block_on<F: Future>(&self, future: F) -> F::Output {
+        #[cfg(feature = "tokio-runtime")]
+        return self.runtime.block_on(future);
+
+        #[cfg(all(feature = "async-std-runtime", not(feature = "tokio-runtime")))]
+        return async_std::task::block_on(future);
+
+        #[cfg(all(
+            feature = "smol-runtime",
+            not(any(feature = "tokio-runtime", feature = "async-std-runtime"))
+        ))]
+        return smol::block_on(future);
+    }
+
+    /// Runs the task in the background, also while no `block_on` is running
+    pub(crate) fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        #[cfg(feature = "tokio-runtime")]
+        drop(self.runtime.spawn(task));
+
+        #[cfg(not(feature = "tokio-runtime"))]
+        spawn(task);
+    }
+}
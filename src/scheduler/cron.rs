@@ -0,0 +1,155 @@
+use super::local_instant;
+use crate::{HassError, HassResult};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+use std::str::FromStr;
+
+/// how many years ahead a `Cron` is looked for, enough for the 29th of February
+const MAX_YEARS: usize = 8;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A cron expression, evaluated in local time
+///
+/// The five fields are the minute, the hour, the day of the month, the month and the day of the
+/// week, e.g. `30 7 * * mon-fri`. Each field is `*`, a value, a range `a-b`, a list `a,b` or a
+/// step `*/n` or `a-b/n`; the months and the days of the week can be named, `0` and `7` being
+/// Sunday. As with cron, when both days are restricted, i.e. neither field starts with `*`, a
+/// day matching either of them matches.
+///
+/// The local times skipped when the clocks go forward fire once the clocks went forward, the
+/// local times repeated when they go back fire once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    #[allow(clippy::result_large_err)]
+    pub fn new(expression: &str) -> HassResult<Self> {
+        let invalid = || HassError::InvalidCron(expression.to_owned());
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(invalid());
+        };
+
+        let weekday_bits = parse_field(weekdays, 0, 7, &WEEKDAYS).ok_or_else(invalid)?;
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: parse_field(minutes, 0, 59, &[]).ok_or_else(invalid)?,
+            hours: parse_field(hours, 0, 23, &[]).ok_or_else(invalid)? as u32,
+            days: parse_field(days, 1, 31, &[]).ok_or_else(invalid)? as u32,
+            months: parse_field(months, 1, 12, &MONTHS).ok_or_else(invalid)? as u16,
+            // 7 is Sunday too
+            weekdays: ((weekday_bits | weekday_bits >> 7) & 0x7f) as u8,
+            // `*/2` restricts the days but, as with cron, does not count as restricted
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+
+    /// The next time matching the expression after `after`, in the time zone `tz`
+    ///
+    /// `None` if no date matches, e.g. `0 0 30 2 *`.
+    pub fn next_after(&self, tz: Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let today = after.with_timezone(&tz).date_naive();
+        today
+            .iter_days()
+            .take(MAX_YEARS * 366)
+            .filter(|day| self.matches_day(*day))
+            .find_map(|day| {
+                self.times().find_map(|time| {
+                    let time = local_instant(tz, day.and_time(time));
+                    (time > after).then_some(time)
+                })
+            })
+    }
+
+    fn matches_day(&self, day: NaiveDate) -> bool {
+        if self.months & 1 << day.month() == 0 {
+            return false;
+        }
+        let in_days = self.days & 1 << day.day() != 0;
+        let in_weekdays = self.weekdays & 1 << day.weekday().num_days_from_sunday() != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => in_days || in_weekdays,
+            _ => in_days && in_weekdays,
+        }
+    }
+
+    /// the matching times of a day, in order
+    fn times(&self) -> impl Iterator<Item = NaiveTime> + '_ {
+        (0..24)
+            .filter(|hour| self.hours & 1 << hour != 0)
+            .flat_map(move |hour| {
+                (0..60)
+                    .filter(|minute| self.minutes & 1 << minute != 0)
+                    .filter_map(move |minute| NaiveTime::from_hms_opt(hour, minute, 0))
+            })
+    }
+}
+
+impl FromStr for Cron {
+    type Err = HassError;
+
+    fn from_str(expression: &str) -> HassResult<Self> {
+        Self::new(expression)
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+/// parses a field into the bit set of its values, `names` being the names of the values from
+/// `min`
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Option<u64> {
+    let value = |value: &str| -> Option<u32> {
+        let value = match names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(value))
+        {
+            Some(position) => position as u32 + min,
+            None => value.parse().ok()?,
+        };
+        (min..=max).contains(&value).then_some(value)
+    };
+
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `a/n` goes from `a` to the maximum
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Some(bits)
+}
@@ -0,0 +1,216 @@
+//! Schedules in the time zone and at the location of the server
+//!
+//! A `Location`, built from the `HassConfig` of the server, computes the sunrise, the sunset,
+//! the dawn, the dusk and the elevation of the sun locally, without the `sun.sun` entity. A
+//! `Schedule` is a daily time, a sun event moved by an offset or a `Cron` expression, all of
+//! them evaluated in the local time of the server, and `Scheduler` runs callbacks on them.
+//! `Schedule::next_after` tells when a schedule fires next, so schedules can be checked offline.
+//!
+//! ```no_run
+//! # async fn run() -> hass_rs::HassResult<()> {
+//! use chrono::TimeDelta;
+//! use hass_rs::scheduler::{Cron, Schedule, Scheduler, SunEvent};
+//! use hass_rs::HassClient;
+//!
+//! let mut client = HassClient::new("ws://localhost:8123/api/websocket").await?;
+//! client.auth_with_longlivedtoken("token").await?;
+//!
+//! let scheduler = Scheduler::from_client(&mut client).await?;
+//! let before_sunset = Schedule::Sun {
+//!     event: SunEvent::Sunset,
+//!     offset: TimeDelta::minutes(-30),
+//! };
+//! let _lights = scheduler.schedule(before_sunset, |at| println!("30 min before sunset: {at}"));
+//! let _alarm = scheduler.schedule(
+//!     Schedule::Cron(Cron::new("30 7 * * mon-fri")?),
+//!     |at| println!("wake up: {at}"),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+mod cron;
+mod sun;
+
+pub use cron::*;
+pub use sun::*;
+
+use crate::types::HassConfig;
+use crate::{runtime, HassClient, HassError, HassResult};
+
+use chrono::{DateTime, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use futures_util::future::{AbortHandle, Abortable};
+use std::time::{Duration, SystemTime};
+
+/// how many days ahead a sun event is looked for, enough for the polar night
+const MAX_SUN_DAYS: usize = 190;
+/// the longest sleep of a scheduled callback before checking the clock again, so that the
+/// changes of the clock and the suspends of the host delay it at most that much
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Where the server is, see `HassConfig`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    /// degrees, positive to the north
+    pub latitude: f64,
+    /// degrees, positive to the east
+    pub longitude: f64,
+    /// meters above the sea level
+    pub elevation: i32,
+    pub tz: Tz,
+}
+
+impl Location {
+    /// The location of the server, `None` if its time zone is not an IANA time zone name
+    pub fn from_config(config: &HassConfig) -> Option<Self> {
+        Some(Self {
+            latitude: config.latitude,
+            longitude: config.longitude,
+            elevation: config.elevation,
+            tz: config.tz()?,
+        })
+    }
+
+    /// The next occurrence of the sun event after `after`
+    ///
+    /// `None` if it does not happen in the coming months, e.g. no sunrise during the polar night.
+    pub fn next_sun_event(&self, event: SunEvent, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let yesterday = after.date_naive().pred_opt()?;
+        yesterday
+            .iter_days()
+            .take(MAX_SUN_DAYS)
+            .filter_map(|day| self.sun_event(day, event))
+            .find(|time| *time > after)
+    }
+}
+
+/// When a scheduled callback runs, in the local time of a `Location`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// Every day at a local time
+    Daily(NaiveTime),
+    /// At a sun event moved by `offset`, negative before the event
+    Sun {
+        event: SunEvent,
+        offset: TimeDelta,
+    },
+    Cron(Cron),
+}
+
+impl Schedule {
+    /// The next time the schedule fires after `after`, `None` if it never does
+    pub fn next_after(&self, location: &Location, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Daily(at) => Some(next_time_of_day(location.tz, after, *at)),
+            Self::Sun { event, offset } => location
+                .next_sun_event(*event, after - *offset)
+                .map(|time| time + *offset),
+            Self::Cron(cron) => cron.next_after(location.tz, after),
+        }
+    }
+}
+
+/// Runs callbacks on schedules, see the module documentation
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    location: Location,
+}
+
+impl Scheduler {
+    pub fn new(location: Location) -> Self {
+        Self { location }
+    }
+
+    /// A scheduler at the location of the server the client is connected to
+    pub async fn from_client(client: &mut HassClient) -> HassResult<Self> {
+        let location = Location::from_config(&client.get_config().await?)
+            .ok_or_else(|| HassError::Generic("unknown time zone of the server".to_owned()))?;
+        Ok(Self::new(location))
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Runs `callback` with the scheduled time each time the schedule fires, until the returned
+    /// job is cancelled or dropped
+    pub fn schedule<F>(&self, schedule: Schedule, mut callback: F) -> ScheduledJob
+    where
+        F: FnMut(DateTime<Utc>) + Send + 'static,
+    {
+        let location = self.location;
+        let (handle, registration) = AbortHandle::new_pair();
+        let task = async move {
+            let mut after = now();
+            while let Some(next) = schedule.next_after(&location, after) {
+                sleep_until(next).await;
+                callback(next);
+                after = next;
+            }
+        };
+        runtime::spawn(async move {
+            Abortable::new(task, registration).await.ok();
+        });
+        ScheduledJob { handle }
+    }
+}
+
+/// A callback scheduled by `Scheduler::schedule`, cancelled when dropped
+#[derive(Debug)]
+pub struct ScheduledJob {
+    handle: AbortHandle,
+}
+
+impl ScheduledJob {
+    pub fn cancel(self) {
+        self.handle.abort();
+    }
+}
+
+impl Drop for ScheduledJob {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// the current time, the `clock` feature of chrono is not enabled
+pub(crate) fn now() -> DateTime<Utc> {
+    SystemTime::now().into()
+}
+
+/// sleeps until the time of the clock, checking it every `MAX_SLEEP`
+pub(crate) async fn sleep_until(time: DateTime<Utc>) {
+    loop {
+        let delay = (time - now()).to_std().unwrap_or_default();
+        if delay.is_zero() {
+            return;
+        }
+        runtime::sleep(delay.min(MAX_SLEEP)).await;
+    }
+}
+
+/// The next occurrence of a local time after `after`, see `local_instant`
+pub(crate) fn next_time_of_day(tz: Tz, after: DateTime<Utc>, at: NaiveTime) -> DateTime<Utc> {
+    let today = after.with_timezone(&tz).date_naive();
+    today
+        .iter_days()
+        .map(|day| local_instant(tz, day.and_time(at)))
+        .find(|time| *time > after)
+        .expect("a later day has the time")
+}
+
+/// The instant of a local time
+///
+/// The day the clocks go forward a time of the gap is the end of the gap, the first valid
+/// instant after it, and the day they go back the first of the repeated times is kept.
+pub(crate) fn local_instant(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    let mut time = local;
+    loop {
+        if let Some(instant) = tz.from_local_datetime(&time).earliest() {
+            return instant.with_timezone(&Utc);
+        }
+        // the gaps are whole minutes, at most a few hours long
+        time += TimeDelta::minutes(1);
+    }
+}
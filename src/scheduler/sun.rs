@@ -0,0 +1,144 @@
+use super::Location;
+
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+
+/// The depression of the center of the sun at sunrise and sunset, the refraction and the radius
+/// of the sun included
+const SUNRISE_DEPRESSION: f64 = 0.833;
+/// The depression of the center of the sun at civil dawn and dusk
+const CIVIL_DEPRESSION: f64 = 6.0;
+
+/// The events of the day of the sun
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SunEvent {
+    /// The civil dawn, the sun 6° below the horizon in the morning
+    Dawn,
+    Sunrise,
+    /// The sun at its highest
+    Noon,
+    Sunset,
+    /// The civil dusk, the sun 6° below the horizon in the evening
+    Dusk,
+}
+
+/// the position of the sun at an instant, in degrees and minutes
+struct SolarPosition {
+    declination: f64,
+    equation_of_time: f64,
+}
+
+impl SolarPosition {
+    /// [NOAA solar calculations](https://gml.noaa.gov/grad/solcalc/calcdetails.html)
+    fn at(time: DateTime<Utc>) -> Self {
+        let julian_day = time.timestamp_millis() as f64 / 86_400_000.0 + 2440587.5;
+        let t = (julian_day - 2451545.0) / 36525.0;
+
+        let mean_longitude = (280.46646 + t * (36000.76983 + t * 0.0003032)).rem_euclid(360.0);
+        let mean_anomaly = 357.52911 + t * (35999.05029 - 0.0001537 * t);
+        let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+        let m = mean_anomaly.to_radians();
+        let center = m.sin() * (1.914602 - t * (0.004817 + 0.000014 * t))
+            + (2.0 * m).sin() * (0.019993 - 0.000101 * t)
+            + (3.0 * m).sin() * 0.000289;
+        let omega = (125.04 - 1934.136 * t).to_radians();
+        let apparent_longitude = mean_longitude + center - 0.00569 - 0.00478 * omega.sin();
+        let mean_obliquity =
+            23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+        let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+
+        let declination = (obliquity.sin() * apparent_longitude.to_radians().sin()).asin();
+        let y = (obliquity / 2.0).tan().powi(2);
+        let l = mean_longitude.to_radians();
+        let equation_of_time = 4.0
+            * (y * (2.0 * l).sin() - 2.0 * eccentricity * m.sin()
+                + 4.0 * eccentricity * y * m.sin() * (2.0 * l).cos()
+                - 0.5 * y * y * (4.0 * l).sin()
+                - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+            .to_degrees();
+
+        Self {
+            declination,
+            equation_of_time,
+        }
+    }
+}
+
+impl Location {
+    /// The elevation of the sun above the horizon, in degrees, negative below it
+    ///
+    /// It is the geometric elevation of the center of the sun, without the refraction.
+    pub fn solar_elevation(&self, time: DateTime<Utc>) -> f64 {
+        let position = SolarPosition::at(time);
+        let minutes = time.timestamp_millis().rem_euclid(86_400_000) as f64 / 60_000.0;
+        let solar_time = minutes + position.equation_of_time + 4.0 * self.longitude;
+        let hour_angle = (solar_time / 4.0 - 180.0).to_radians();
+        let latitude = self.latitude.to_radians();
+        let cos_zenith = latitude.sin() * position.declination.sin()
+            + latitude.cos() * position.declination.cos() * hour_angle.cos();
+        90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees()
+    }
+
+    /// The event of the solar day of `date`, the day whose solar noon falls on `date` in UTC
+    /// shifted by the longitude
+    ///
+    /// `None` if the sun does not reach the event that day, e.g. no sunset during the polar day.
+    pub fn sun_event(&self, date: NaiveDate, event: SunEvent) -> Option<DateTime<Utc>> {
+        let depression = match event {
+            SunEvent::Noon => return Some(self.solar_noon(date)),
+            SunEvent::Sunrise | SunEvent::Sunset => SUNRISE_DEPRESSION + self.horizon_dip(),
+            SunEvent::Dawn | SunEvent::Dusk => CIVIL_DEPRESSION,
+        };
+        let rising = matches!(event, SunEvent::Dawn | SunEvent::Sunrise);
+        self.time_at_elevation(date, -depression, rising)
+    }
+
+    /// When the center of the sun is at `elevation` degrees above the horizon, the solar day of
+    /// `date`, in the morning if `rising` and in the evening otherwise
+    ///
+    /// `None` if the sun does not reach the elevation that day.
+    pub fn time_at_elevation(
+        &self,
+        date: NaiveDate,
+        elevation: f64,
+        rising: bool,
+    ) -> Option<DateTime<Utc>> {
+        let latitude = self.latitude.to_radians();
+        let zenith = (90.0 - elevation).to_radians();
+        // refined with the position of the sun at the previous estimate
+        let mut time = self.solar_noon(date);
+        for _ in 0..3 {
+            let position = SolarPosition::at(time);
+            let cos_hour_angle = zenith.cos() / (latitude.cos() * position.declination.cos())
+                - latitude.tan() * position.declination.tan();
+            if !(-1.0..=1.0).contains(&cos_hour_angle) {
+                return None;
+            }
+            let hour_angle = cos_hour_angle.acos().to_degrees();
+            let noon = 720.0 - 4.0 * self.longitude - position.equation_of_time;
+            let minutes = match rising {
+                true => noon - 4.0 * hour_angle,
+                false => noon + 4.0 * hour_angle,
+            };
+            time = midnight(date) + TimeDelta::milliseconds((minutes * 60_000.0) as i64);
+        }
+        Some(time)
+    }
+
+    fn solar_noon(&self, date: NaiveDate) -> DateTime<Utc> {
+        let mut time = midnight(date) + TimeDelta::hours(12);
+        for _ in 0..2 {
+            let noon = 720.0 - 4.0 * self.longitude - SolarPosition::at(time).equation_of_time;
+            time = midnight(date) + TimeDelta::milliseconds((noon * 60_000.0) as i64);
+        }
+        time
+    }
+
+    /// how much lower the horizon is seen from above the sea level, in degrees
+    fn horizon_dip(&self) -> f64 {
+        2.076 * f64::from(self.elevation.max(0)).sqrt() / 60.0
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(Default::default()).and_utc()
+}
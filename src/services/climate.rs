@@ -0,0 +1,73 @@
+//! Services of the `climate` domain
+//!
+//! [Climate](https://www.home-assistant.io/integrations/climate/#actions)
+
+use crate::services::service_call;
+use crate::types::{HvacMode, Target};
+
+use serde::Serialize;
+
+/// Sets the target temperature, or the target range for `heat_cool` mode
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct SetTemperature {
+    #[serde(skip)]
+    pub target: Target,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_temp_high: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_temp_low: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hvac_mode: Option<HvacMode>,
+}
+
+service_call!(SetTemperature, "climate", "set_temperature");
+
+/// Sets the HVAC operation mode
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SetHvacMode {
+    #[serde(skip)]
+    pub target: Target,
+    pub hvac_mode: HvacMode,
+}
+
+service_call!(SetHvacMode, "climate", "set_hvac_mode", hvac_mode: HvacMode);
+
+/// Sets the preset mode, e.g. `eco` or `away`
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SetPresetMode {
+    #[serde(skip)]
+    pub target: Target,
+    pub preset_mode: String,
+}
+
+service_call!(SetPresetMode, "climate", "set_preset_mode", preset_mode: String);
+
+/// Sets the fan mode, e.g. `low` or `auto`
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SetFanMode {
+    #[serde(skip)]
+    pub target: Target,
+    pub fan_mode: String,
+}
+
+service_call!(SetFanMode, "climate", "set_fan_mode", fan_mode: String);
+
+/// Turns the climate device on
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct TurnOn {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(TurnOn, "climate", "turn_on");
+
+/// Turns the climate device off
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct TurnOff {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(TurnOff, "climate", "turn_off");
@@ -0,0 +1,60 @@
+//! Services of the `cover` domain
+//!
+//! [Cover](https://www.home-assistant.io/integrations/cover/#actions)
+
+use crate::services::service_call;
+use crate::types::Target;
+
+use serde::Serialize;
+
+/// Opens one or more covers
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct OpenCover {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(OpenCover, "cover", "open_cover");
+
+/// Closes one or more covers
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct CloseCover {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(CloseCover, "cover", "close_cover");
+
+/// Stops the movement of one or more covers
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct StopCover {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(StopCover, "cover", "stop_cover");
+
+/// Moves one or more covers to a position, 0 being closed and 100 fully open
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SetCoverPosition {
+    #[serde(skip)]
+    pub target: Target,
+    pub position: u8,
+}
+
+service_call!(SetCoverPosition, "cover", "set_cover_position", position: u8);
+
+/// Moves the tilt of one or more covers to a position, 0 being closed and 100 fully open
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SetCoverTiltPosition {
+    #[serde(skip)]
+    pub target: Target,
+    pub tilt_position: u8,
+}
+
+service_call!(
+    SetCoverTiltPosition,
+    "cover",
+    "set_cover_tilt_position",
+    tilt_position: u8
+);
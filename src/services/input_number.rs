@@ -0,0 +1,36 @@
+//! Services of the `input_number` domain
+//!
+//! [Input Number](https://www.home-assistant.io/integrations/input_number/#actions)
+
+use crate::services::service_call;
+use crate::types::Target;
+
+use serde::Serialize;
+
+/// Sets the value of an input number
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SetValue {
+    #[serde(skip)]
+    pub target: Target,
+    pub value: f64,
+}
+
+service_call!(SetValue, "input_number", "set_value", value: f64);
+
+/// Increments the value by one step
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct Increment {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(Increment, "input_number", "increment");
+
+/// Decrements the value by one step
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct Decrement {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(Decrement, "input_number", "decrement");
@@ -0,0 +1,82 @@
+//! Services of the `light` domain
+//!
+//! [Light](https://www.home-assistant.io/integrations/light/#actions)
+
+use crate::services::service_call;
+use crate::types::Target;
+
+use serde::{Deserialize, Serialize};
+
+/// Turns one or more lights on and adjusts their properties
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct TurnOn {
+    #[serde(skip)]
+    pub target: Target,
+    /// seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_pct: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_step: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_step_pct: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rgb_color: Option<(u8, u8, u8)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rgbw_color: Option<(u8, u8, u8, u8)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hs_color: Option<(f64, f64)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xy_color: Option<(f64, f64)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_temp_kelvin: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flash: Option<Flash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+service_call!(TurnOn, "light", "turn_on");
+
+/// Turns one or more lights off
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct TurnOff {
+    #[serde(skip)]
+    pub target: Target,
+    /// seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flash: Option<Flash>,
+}
+
+service_call!(TurnOff, "light", "turn_off");
+
+/// Toggles one or more lights
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct Toggle {
+    #[serde(skip)]
+    pub target: Target,
+    /// seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_pct: Option<u8>,
+}
+
+service_call!(Toggle, "light", "toggle");
+
+/// This is part of TurnOn and TurnOff
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Flash {
+    Short,
+    Long,
+}
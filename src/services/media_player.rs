@@ -0,0 +1,90 @@
+//! Services of the `media_player` domain
+//!
+//! [Media Player](https://www.home-assistant.io/integrations/media_player/#actions)
+
+use crate::services::service_call;
+use crate::types::Target;
+
+use serde::{Deserialize, Serialize};
+
+/// Starts playing the provided media
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct PlayMedia {
+    #[serde(skip)]
+    pub target: Target,
+    pub media_content_id: String,
+    /// e.g. `music`, `video`, `playlist` or `url`
+    pub media_content_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enqueue: Option<Enqueue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub announce: Option<bool>,
+}
+
+service_call!(
+    PlayMedia,
+    "media_player",
+    "play_media",
+    media_content_id: String,
+    media_content_type: String;
+    enqueue,
+    announce
+);
+
+/// This is part of PlayMedia
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Enqueue {
+    Add,
+    Next,
+    Play,
+    Replace,
+}
+
+/// Resumes the playback
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct MediaPlay {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(MediaPlay, "media_player", "media_play");
+
+/// Pauses the playback
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct MediaPause {
+    #[serde(skip)]
+    pub target: Target,
+}
+
+service_call!(MediaPause, "media_player", "media_pause");
+
+/// Sets the volume, from 0.0 to 1.0
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct VolumeSet {
+    #[serde(skip)]
+    pub target: Target,
+    pub volume_level: f64,
+}
+
+service_call!(VolumeSet, "media_player", "volume_set", volume_level: f64);
+
+/// Mutes or unmutes the media player
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct VolumeMute {
+    #[serde(skip)]
+    pub target: Target,
+    pub is_volume_muted: bool,
+}
+
+service_call!(VolumeMute, "media_player", "volume_mute", is_volume_muted: bool);
+
+/// Selects the input source
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SelectSource {
+    #[serde(skip)]
+    pub target: Target,
+    pub source: String,
+}
+
+service_call!(SelectSource, "media_player", "select_source", source: String);
@@ -0,0 +1,101 @@
+//! Typed service calls
+//!
+//! Each request serializes to a `call_service` command, the fields being the service data and
+//! `target` the entities, devices or areas the service is applied to.
+//! They are sent with `HassClient::call_service_typed`.
+
+use crate::types::Target;
+use crate::HassResult;
+
+use serde_json::Value;
+
+pub mod climate;
+pub mod cover;
+pub mod input_number;
+pub mod light;
+pub mod media_player;
+pub mod notify;
+
+/// A service call that can be sent with `HassClient::call_service_typed`
+pub trait ServiceCall {
+    /// The domain of the service, e.g. `light`
+    fn domain(&self) -> &str;
+
+    /// The name of the service, e.g. `turn_on`
+    fn service(&self) -> &str;
+
+    /// The entities, devices or areas the service is applied to
+    fn target(&self) -> Option<&Target>;
+
+    /// The service data, `None` if the service has no field set
+    #[allow(clippy::result_large_err)]
+    fn service_data(&self) -> HassResult<Option<Value>>;
+}
+
+/// implements ServiceCall for a request with a `target` field, the other fields being serialized
+/// as the service data
+///
+/// The required fields of the service, listed after the service name, are the arguments of `new`
+/// and the optional ones listed after them, if any, start as `None`.
+macro_rules! service_call {
+    (@impl $request:ty, $domain:literal, $service:literal) => {
+        impl $crate::services::ServiceCall for $request {
+            fn domain(&self) -> &str {
+                $domain
+            }
+
+            fn service(&self) -> &str {
+                $service
+            }
+
+            fn target(&self) -> Option<&$crate::types::Target> {
+                Some(&self.target)
+            }
+
+            fn service_data(&self) -> $crate::HassResult<Option<serde_json::Value>> {
+                $crate::services::to_service_data(self)
+            }
+        }
+    };
+    ($request:ty, $domain:literal, $service:literal) => {
+        impl $request {
+            // some requests have no other field than the target
+            #[allow(clippy::needless_update)]
+            pub fn new(target: $crate::types::Target) -> Self {
+                Self {
+                    target,
+                    ..Default::default()
+                }
+            }
+        }
+
+        $crate::services::service_call!(@impl $request, $domain, $service);
+    };
+    (
+        $request:ty, $domain:literal, $service:literal,
+        $($field:ident: $type:ty),+ $(; $($optional:ident),+)?
+    ) => {
+        impl $request {
+            pub fn new(target: $crate::types::Target, $($field: impl Into<$type>),+) -> Self {
+                Self {
+                    target,
+                    $($field: $field.into(),)+
+                    $($($optional: None,)+)?
+                }
+            }
+        }
+
+        $crate::services::service_call!(@impl $request, $domain, $service);
+    };
+}
+
+pub(crate) use service_call;
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn to_service_data<T: serde::Serialize>(request: &T) -> HassResult<Option<Value>> {
+    let value = serde_json::to_value(request)?;
+    match value {
+        Value::Object(map) if map.is_empty() => Ok(None),
+        value => Ok(Some(value)),
+    }
+}
@@ -0,0 +1,71 @@
+//! Services of the `notify` domain
+//!
+//! [Notify](https://www.home-assistant.io/integrations/notify/)
+
+use crate::services::{service_call, to_service_data, ServiceCall};
+use crate::types::Target;
+use crate::HassResult;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Sends a notification through a legacy notify service, e.g. `notify.mobile_app_pixel`
+///
+/// These services are named after the notifier, so the service name is part of the request.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct Send {
+    /// the notifier, e.g. `mobile_app_pixel`
+    #[serde(skip)]
+    pub service: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// the notifier specific recipients
+    #[serde(rename = "target", skip_serializing_if = "Option::is_none")]
+    pub recipients: Option<Vec<String>>,
+    /// the notifier specific data, e.g. actions or images of mobile notifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl Send {
+    pub fn new(service: &str, message: &str) -> Self {
+        Self {
+            service: service.to_owned(),
+            message: message.to_owned(),
+            title: None,
+            recipients: None,
+            data: None,
+        }
+    }
+}
+
+impl ServiceCall for Send {
+    fn domain(&self) -> &str {
+        "notify"
+    }
+
+    fn service(&self) -> &str {
+        &self.service
+    }
+
+    fn target(&self) -> Option<&Target> {
+        None
+    }
+
+    fn service_data(&self) -> HassResult<Option<Value>> {
+        to_service_data(self)
+    }
+}
+
+/// Sends a notification message through notify entities
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SendMessage {
+    #[serde(skip)]
+    pub target: Target,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+service_call!(SendMessage, "notify", "send_message", message: String; title);
@@ -0,0 +1,64 @@
+//! Typed subscriptions
+
+use crate::{HassResult, WSEvent};
+
+use async_channel::Receiver;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// EventReceiver receives the events of a `subscribe_event` subscription.
+///
+/// Dropping it will unsubscribe on the next event received from the server.
+#[derive(Debug)]
+pub struct EventReceiver {
+    rx: Receiver<WSEvent>,
+}
+
+impl EventReceiver {
+    pub(crate) fn new(rx: Receiver<WSEvent>) -> Self {
+        Self { rx }
+    }
+
+    /// Receives the next event of the subscription.
+    ///
+    /// Returns `None` once the connection is gone or the subscription was unsubscribed.
+    pub async fn recv(&mut self) -> Option<WSEvent> {
+        self.rx.recv().await.ok()
+    }
+}
+
+/// HassSubscription receives the messages of a subscription whose payload is not a `HassEvent`,
+/// e.g. the logbook event stream, and decodes them into `T`.
+///
+/// Dropping it will unsubscribe on the next message received from the server, the same way as
+/// dropping the receiver returned by `subscribe_event`.
+#[derive(Debug)]
+pub struct HassSubscription<T> {
+    id: u64,
+    rx: Receiver<Value>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> HassSubscription<T> {
+    pub(crate) fn new(id: u64, rx: Receiver<Value>) -> Self {
+        Self {
+            id,
+            rx,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The subscription id, to be used with `unsubscribe_event`
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Receives the next message of the subscription.
+    ///
+    /// Returns `None` once the connection is gone or the subscription was unsubscribed.
+    pub async fn recv(&mut self) -> Option<HassResult<T>> {
+        let value = self.rx.recv().await.ok()?;
+        Some(serde_json::from_value(value).map_err(Into::into))
+    }
+}
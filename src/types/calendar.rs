@@ -0,0 +1,42 @@
+use crate::types::EntityId;
+use serde::{Deserialize, Serialize};
+
+/// A calendar entity, as listed by the REST API
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct HassCalendar {
+    pub entity_id: EntityId,
+    pub name: String,
+}
+
+/// An event of a calendar
+///
+/// [Calendar](https://developers.home-assistant.io/docs/api/rest/#get-apicalendarscalendar-entity-id)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CalendarEvent {
+    pub summary: String,
+    pub start: CalendarTime,
+    pub end: CalendarTime,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub uid: Option<String>,
+    pub recurrence_id: Option<String>,
+    /// the recurrence rule of a recurring event, e.g. `FREQ=WEEKLY`
+    pub rrule: Option<String>,
+}
+
+impl CalendarEvent {
+    /// Whether the event lasts whole days, its bounds are dates without a time
+    pub fn is_all_day(&self) -> bool {
+        self.start.date_time.is_none()
+    }
+}
+
+/// The start or the end of a calendar event, either a date-time or a date for all-day events
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CalendarTime {
+    /// ISO 8601 date-time, e.g. `2024-06-01T10:00:00+02:00`
+    #[serde(rename = "dateTime")]
+    pub date_time: Option<String>,
+    /// ISO 8601 date, e.g. `2024-06-01`
+    pub date: Option<String>,
+}
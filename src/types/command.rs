@@ -1,6 +1,10 @@
+use crate::types::{
+    CoreConfigUpdate, IntegrationType, StatisticImportMetadata, StatisticImportValue,
+    StatisticType, StatisticWindow, StatisticsPeriod, Target,
+};
 use serde::Serialize;
 use serde_json::Value;
-use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+use std::collections::HashMap;
 
 /// This enum defines the type of commands that the client is allowed to send to the Websocket server
 #[derive(Debug, Serialize)]
@@ -15,20 +19,61 @@ pub(crate) enum Command {
     GetStates(Ask),
     GetPanels(Ask),
     GetAreaRegistryList(Ask),
+    GetFloorRegistryList(Ask),
+    GetLabelRegistryList(Ask),
     GetDeviceRegistryList(Ask),
     GetEntityRegistryList(Ask),
     ListRepairs(Ask),
+    IgnoreRepair(IgnoreIssue),
     CallService(CallService),
-    #[allow(dead_code)]
-    Close,
+    ListStatisticIds(ListStatisticIds),
+    GetStatisticsMetadata(GetStatisticsMetadata),
+    StatisticsDuringPeriod(StatisticsDuringPeriod),
+    StatisticDuringPeriod(StatisticDuringPeriod),
+    ImportStatistics(ImportStatistics),
+    GetLogbookEvents(Logbook),
+    SubscribeLogbook(Logbook),
+    GetConfigEntries(GetConfigEntries),
+    SubscribeConfigEntries(SubscribeConfigEntries),
+    DisableConfigEntry(DisableConfigEntry),
+    ListConfigFlows(Ask),
+    UpdateCoreConfig(UpdateCoreConfig),
+    Raw(RawCommand),
 }
 
 impl Command {
-    /// This function transform a command into a TungsteniteMessage and needs the last
-    /// gateway sequence in order to send it correctly
-    pub(crate) fn to_tungstenite_message(self) -> TungsteniteMessage {
-        let cmd_str = serde_json::to_string(&self).unwrap();
-        TungsteniteMessage::text(cmd_str)
+    /// the `type` of the command, e.g. `get_states`
+    pub(crate) fn msg_type(&self) -> &str {
+        match self {
+            Self::AuthInit(cmd) => &cmd.msg_type,
+            Self::Ping(cmd)
+            | Self::GetConfig(cmd)
+            | Self::GetServices(cmd)
+            | Self::GetStates(cmd)
+            | Self::GetPanels(cmd)
+            | Self::GetAreaRegistryList(cmd)
+            | Self::GetFloorRegistryList(cmd)
+            | Self::GetLabelRegistryList(cmd)
+            | Self::GetDeviceRegistryList(cmd)
+            | Self::GetEntityRegistryList(cmd)
+            | Self::ListRepairs(cmd)
+            | Self::ListConfigFlows(cmd) => &cmd.msg_type,
+            Self::SubscribeEvent(cmd) => &cmd.msg_type,
+            Self::Unsubscribe(cmd) => &cmd.msg_type,
+            Self::IgnoreRepair(cmd) => &cmd.msg_type,
+            Self::CallService(cmd) => &cmd.msg_type,
+            Self::ListStatisticIds(cmd) => &cmd.msg_type,
+            Self::GetStatisticsMetadata(cmd) => &cmd.msg_type,
+            Self::StatisticsDuringPeriod(cmd) => &cmd.msg_type,
+            Self::StatisticDuringPeriod(cmd) => &cmd.msg_type,
+            Self::ImportStatistics(cmd) => &cmd.msg_type,
+            Self::GetLogbookEvents(cmd) | Self::SubscribeLogbook(cmd) => &cmd.msg_type,
+            Self::GetConfigEntries(cmd) => &cmd.msg_type,
+            Self::SubscribeConfigEntries(cmd) => &cmd.msg_type,
+            Self::DisableConfigEntry(cmd) => &cmd.msg_type,
+            Self::UpdateCoreConfig(cmd) => &cmd.msg_type,
+            Self::Raw(cmd) => &cmd.msg_type,
+        }
     }
 }
 
@@ -75,4 +120,144 @@ pub(crate) struct CallService {
     pub(crate) domain: String,
     pub(crate) service: String,
     pub(crate) service_data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) target: Option<Target>,
+}
+
+//used to list the statistic ids
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct ListStatisticIds {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) statistic_type: Option<StatisticType>,
+}
+
+//used to fetch the metadata of statistics
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct GetStatisticsMetadata {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) statistic_ids: Option<Vec<String>>,
+}
+
+//used to fetch statistic series
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct StatisticsDuringPeriod {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    pub(crate) start_time: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) end_time: Option<String>,
+    pub(crate) statistic_ids: Vec<String>,
+    pub(crate) period: StatisticsPeriod,
+    pub(crate) types: Vec<StatisticType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) units: Option<HashMap<String, String>>,
+}
+
+//used to fetch a single aggregated statistic
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct StatisticDuringPeriod {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    pub(crate) statistic_id: String,
+    pub(crate) types: Vec<StatisticType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) units: Option<HashMap<String, String>>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub(crate) window: Option<StatisticWindow>,
+}
+
+//used to import statistics
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct ImportStatistics {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    pub(crate) metadata: StatisticImportMetadata,
+    pub(crate) stats: Vec<StatisticImportValue>,
+}
+
+//used to fetch or subscribe to the logbook
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct Logbook {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    pub(crate) start_time: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) end_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) entity_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) device_ids: Option<Vec<String>>,
+}
+
+//used to fetch the config entries
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct GetConfigEntries {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) type_filter: Option<Vec<IntegrationType>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) domain: Option<String>,
+}
+
+//used to subscribe to the config entries changes
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct SubscribeConfigEntries {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) type_filter: Option<Vec<IntegrationType>>,
+}
+
+//used to disable or enable a config entry, disabled_by is null to enable it
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct DisableConfigEntry {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    pub(crate) entry_id: String,
+    pub(crate) disabled_by: Option<String>,
+}
+
+//used to ignore or unignore a repair issue
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct IgnoreIssue {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    pub(crate) domain: String,
+    pub(crate) issue_id: String,
+    pub(crate) ignore: bool,
+}
+
+//used to change the core config
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct UpdateCoreConfig {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    #[serde(flatten)]
+    pub(crate) update: CoreConfigUpdate,
+}
+
+//used to send a command not modeled by the library
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct RawCommand {
+    pub(crate) id: u64,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    #[serde(flatten)]
+    pub(crate) payload: serde_json::Map<String, Value>,
 }
@@ -1,59 +1,184 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::fmt;
 
 /// This object represents the Home Assistant Config
 ///
 /// This will get a dump of the current config in Home Assistant.
+/// The fields missing in older Home Assistant versions are defaulted, and the fields added by
+/// newer versions are kept in `extra`.
 /// [Fetch Config](https://developers.home-assistant.io/docs/api/websocket/#fetching-config)
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct HassConfig {
-    pub latitude: f32,
-    pub longitude: f32,
-    pub elevation: u32,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// meters above the sea level, negative below it
+    #[serde(default)]
+    pub elevation: i32,
+    #[serde(default)]
     pub unit_system: UnitSystem,
     pub location_name: String,
     pub time_zone: String,
+    #[serde(default)]
     pub components: Vec<String>,
+    #[serde(default)]
     pub config_dir: String,
+    #[serde(default)]
     pub whitelist_external_dirs: Vec<String>,
+    #[serde(default)]
+    pub allowlist_external_dirs: Vec<String>,
+    #[serde(default)]
+    pub allowlist_external_urls: Vec<String>,
     pub version: String,
+    #[serde(default)]
     pub config_source: String,
+    #[serde(default)]
     pub safe_mode: bool,
+    #[serde(default)]
+    pub recovery_mode: bool,
+    pub state: Option<CoreState>,
     pub external_url: Option<String>,
     pub internal_url: Option<String>,
+    /// ISO 4217 code, e.g. `EUR`
+    pub currency: Option<String>,
+    /// ISO 3166-1 alpha-2 code, e.g. `NL`
+    pub country: Option<String>,
+    /// BCP 47 tag, e.g. `en-GB`
+    pub language: Option<String>,
+    /// the radius of the home zone in meters
+    pub radius: Option<u32>,
+    #[serde(default)]
+    pub debug: bool,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[cfg(feature = "chrono")]
+impl HassConfig {
+    /// The time zone of the server, `None` if it is not an IANA time zone name
+    pub fn tz(&self) -> Option<chrono_tz::Tz> {
+        self.time_zone.parse().ok()
+    }
 }
 
 /// This is part of HassConfig
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct UnitSystem {
+    #[serde(default)]
     pub length: String,
+    #[serde(default)]
     pub mass: String,
+    #[serde(default)]
     pub pressure: String,
+    #[serde(default)]
     pub temperature: String,
+    #[serde(default)]
     pub volume: String,
+    pub accumulated_precipitation: Option<String>,
+    pub wind_speed: Option<String>,
+    pub area: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of HassConfig
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CoreState {
+    NotRunning,
+    Starting,
+    Running,
+    Stopping,
+    FinalWrite,
+    Stopped,
+    /// a state introduced by a newer Home Assistant version
+    #[serde(other)]
+    Unknown,
+}
+
+/// The changes of the core config sent with `HassClient::update_core_config`, the fields left
+/// to `None` are not changed
+///
+/// [Core config](https://www.home-assistant.io/integrations/homeassistant/#editing-general-settings-in-yaml)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct CoreConfigUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elevation: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub radius: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_system: Option<UnitSystemName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal_url: Option<String>,
+}
+
+/// This is part of CoreConfigUpdate
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitSystemName {
+    Metric,
+    UsCustomary,
+}
+
+/// The outcome of the check of the configuration files, see `HassRestClient::check_config`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ConfigCheck {
+    /// `valid` or `invalid`
+    pub result: String,
+    pub errors: Option<String>,
+    pub warnings: Option<String>,
+}
+
+impl ConfigCheck {
+    pub fn is_valid(&self) -> bool {
+        self.result == "valid"
+    }
 }
 
 impl fmt::Display for HassConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "HassConfig {{\n")?;
-        write!(f, "  latitude: {},\n", self.latitude)?;
-        write!(f, "  longitude: {},\n", self.longitude)?;
-        write!(f, "  elevation: {},\n", self.elevation)?;
-        write!(f, "  unit_system: {:?},\n", self.unit_system)?;
-        write!(f, "  location_name: {},\n", self.location_name)?;
-        write!(f, "  time_zone: {},\n", self.time_zone)?;
-        write!(f, "  components: {:?},\n", self.components)?;
-        write!(f, "  config_dir: {},\n", self.config_dir)?;
-        write!(
+        writeln!(f, "HassConfig {{")?;
+        writeln!(f, "  latitude: {},", self.latitude)?;
+        writeln!(f, "  longitude: {},", self.longitude)?;
+        writeln!(f, "  elevation: {},", self.elevation)?;
+        writeln!(f, "  unit_system: {:?},", self.unit_system)?;
+        writeln!(f, "  location_name: {},", self.location_name)?;
+        writeln!(f, "  time_zone: {},", self.time_zone)?;
+        writeln!(f, "  components: {:?},", self.components)?;
+        writeln!(f, "  config_dir: {},", self.config_dir)?;
+        writeln!(
             f,
-            "  whitelist_external_dirs: {:?},\n",
+            "  whitelist_external_dirs: {:?},",
             self.whitelist_external_dirs
         )?;
-        write!(f, "  version: {},\n", self.version)?;
-        write!(f, "  config_source: {},\n", self.config_source)?;
-        write!(f, "  safe_mode: {},\n", self.safe_mode)?;
-        write!(f, "  external_url: {:?},\n", self.external_url)?;
-        write!(f, "  internal_url: {:?},\n", self.internal_url)?;
+        writeln!(f, "  version: {},", self.version)?;
+        writeln!(f, "  config_source: {},", self.config_source)?;
+        writeln!(f, "  safe_mode: {},", self.safe_mode)?;
+        writeln!(f, "  recovery_mode: {},", self.recovery_mode)?;
+        writeln!(f, "  state: {:?},", self.state)?;
+        writeln!(f, "  external_url: {:?},", self.external_url)?;
+        writeln!(f, "  internal_url: {:?},", self.internal_url)?;
+        writeln!(f, "  currency: {:?},", self.currency)?;
+        writeln!(f, "  country: {:?},", self.country)?;
+        writeln!(f, "  language: {:?},", self.language)?;
+        writeln!(f, "  radius: {:?},", self.radius)?;
         write!(f, "}}")?;
         Ok(())
     }
@@ -61,12 +186,19 @@ impl fmt::Display for HassConfig {
 
 impl fmt::Display for UnitSystem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "UnitSystem {{\n")?;
-        write!(f, "  length: {},\n", self.length)?;
-        write!(f, "  mass: {},\n", self.mass)?;
-        write!(f, "  pressure: {},\n", self.pressure)?;
-        write!(f, "  temperature: {},\n", self.temperature)?;
-        write!(f, "  volume: {},\n", self.volume)?;
+        writeln!(f, "UnitSystem {{")?;
+        writeln!(f, "  length: {},", self.length)?;
+        writeln!(f, "  mass: {},", self.mass)?;
+        writeln!(f, "  pressure: {},", self.pressure)?;
+        writeln!(f, "  temperature: {},", self.temperature)?;
+        writeln!(f, "  volume: {},", self.volume)?;
+        writeln!(
+            f,
+            "  accumulated_precipitation: {:?},",
+            self.accumulated_precipitation
+        )?;
+        writeln!(f, "  wind_speed: {:?},", self.wind_speed)?;
+        writeln!(f, "  area: {:?},", self.area)?;
         write!(f, "}}")?;
         Ok(())
     }
@@ -0,0 +1,121 @@
+use crate::types::{HassRegistryDevice, HassRegistryEntity};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// This object represents a Home Assistant Config Entry, the configured instance of an integration
+///
+/// [Config Entries](https://developers.home-assistant.io/docs/config_entries_index)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct HassConfigEntry {
+    pub entry_id: String,
+    pub domain: String,
+    pub title: String,
+    pub source: String,
+    pub state: ConfigEntryState,
+    pub supports_options: bool,
+    pub supports_remove_device: bool,
+    pub supports_unload: bool,
+    #[serde(default)]
+    pub supports_reconfigure: bool,
+    pub pref_disable_new_entities: bool,
+    pub pref_disable_polling: bool,
+    pub disabled_by: Option<String>,
+    pub reason: Option<String>,
+    pub error_reason_translation_key: Option<String>,
+    pub error_reason_translation_placeholders: Option<HashMap<String, Value>>,
+    pub num_subentries: Option<u32>,
+    pub created_at: Option<f64>,
+    pub modified_at: Option<f64>,
+}
+
+impl HassConfigEntry {
+    /// The devices created by this config entry, matched against `HassRegistryDevice::config_entries`
+    pub fn devices<'a>(&self, devices: &'a [HassRegistryDevice]) -> Vec<&'a HassRegistryDevice> {
+        devices
+            .iter()
+            .filter(|device| device.config_entries.contains(&self.entry_id))
+            .collect()
+    }
+
+    /// The entities created by this config entry, matched against `HassRegistryEntity::config_entry_id`
+    pub fn entities<'a>(&self, entities: &'a [HassRegistryEntity]) -> Vec<&'a HassRegistryEntity> {
+        entities
+            .iter()
+            .filter(|entity| entity.config_entry_id.as_ref() == Some(&self.entry_id))
+            .collect()
+    }
+
+    /// Returns true if the config entry failed to set up or to unload
+    pub fn is_failed(&self) -> bool {
+        matches!(
+            self.state,
+            ConfigEntryState::SetupError
+                | ConfigEntryState::SetupRetry
+                | ConfigEntryState::MigrationError
+                | ConfigEntryState::FailedUnload
+        )
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl HassConfigEntry {
+    /// The moment the config entry was created, `None` for older Home Assistant versions
+    pub fn created_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.created_at.and_then(super::timestamp::from_epoch)
+    }
+
+    /// The moment the config entry was last modified, `None` for older Home Assistant versions
+    pub fn modified_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.modified_at.and_then(super::timestamp::from_epoch)
+    }
+}
+
+/// This is part of HassConfigEntry
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigEntryState {
+    Loaded,
+    SetupError,
+    MigrationError,
+    SetupRetry,
+    NotLoaded,
+    FailedUnload,
+    SetupInProgress,
+    UnloadInProgress,
+    /// a state introduced by a newer Home Assistant version
+    #[serde(other)]
+    Unknown,
+}
+
+/// The integration types used to filter the config entries
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrationType {
+    Device,
+    Entity,
+    Hardware,
+    Helper,
+    Hub,
+    Service,
+    System,
+}
+
+/// This is a change received on the `config_entries/subscribe` subscription
+///
+/// The first message lists all the config entries with `change_type` set to `None`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ConfigEntryChange {
+    #[serde(rename = "type")]
+    pub change_type: Option<ConfigEntryChangeType>,
+    pub entry: HassConfigEntry,
+}
+
+/// This is part of ConfigEntryChange
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigEntryChangeType {
+    Added,
+    Removed,
+    Updated,
+}
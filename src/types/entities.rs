@@ -1,3 +1,4 @@
+use crate::types::EntityId;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt;
@@ -7,7 +8,7 @@ use std::fmt;
 /// [Entity](https://developers.home-assistant.io/docs/core/entity/)
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct HassEntity {
-    pub entity_id: String,
+    pub entity_id: EntityId,
     pub last_changed: String,
     pub state: String,
     pub attributes: Value,
@@ -15,6 +16,19 @@ pub struct HassEntity {
     pub context: Option<Context>, //changed
 }
 
+#[cfg(feature = "chrono")]
+impl HassEntity {
+    /// The moment the state last changed, `None` if the timestamp is malformed
+    pub fn last_changed_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::parse_iso(&self.last_changed)
+    }
+
+    /// The moment the state or an attribute was last updated, `None` if the timestamp is malformed
+    pub fn last_updated_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::parse_iso(&self.last_updated)
+    }
+}
+
 /// General construct used by HassEntity and HassEvent
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Context {
@@ -25,13 +39,13 @@ pub struct Context {
 
 impl fmt::Display for HassEntity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "HassEntity {{\n")?;
-        write!(f, "  entity_id: {},\n", self.entity_id)?;
-        write!(f, "  state: {},\n", self.state)?;
-        write!(f, "  last_changed: {},\n", self.last_changed)?;
-        write!(f, "  last_updated: {},\n", self.last_updated)?;
-        write!(f, "  attributes: {:?},\n", self.attributes)?;
-        write!(f, "  context: {:?},\n", self.context)?;
+        writeln!(f, "HassEntity {{")?;
+        writeln!(f, "  entity_id: {},", self.entity_id)?;
+        writeln!(f, "  state: {},", self.state)?;
+        writeln!(f, "  last_changed: {},", self.last_changed)?;
+        writeln!(f, "  last_updated: {},", self.last_updated)?;
+        writeln!(f, "  attributes: {:?},", self.attributes)?;
+        writeln!(f, "  context: {:?},", self.context)?;
         write!(f, "}}")?;
         Ok(())
     }
@@ -0,0 +1,316 @@
+use crate::{HassError, HassResult};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::fmt;
+use std::str::FromStr;
+
+/// This object represents a validated entity id, e.g. `light.kitchen`
+///
+/// Both the domain and the object id are made of lowercase letters, digits and underscores,
+/// without a leading, trailing or double underscore.
+/// [Entity id](https://developers.home-assistant.io/docs/core/entity/#generic-properties)
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct EntityId(String);
+
+impl EntityId {
+    /// Validates the entity id, returns `HassError::InvalidEntityId` if it is malformed
+    #[allow(clippy::result_large_err)]
+    pub fn new(entity_id: impl Into<String>) -> HassResult<Self> {
+        let entity_id = entity_id.into();
+        match entity_id.split_once('.') {
+            Some((domain, object_id)) if is_slug(domain) && is_slug(object_id) => {
+                Ok(Self(entity_id))
+            }
+            _ => Err(HassError::InvalidEntityId(entity_id)),
+        }
+    }
+
+    /// The domain of the entity, e.g. `light` for `light.kitchen`
+    pub fn domain(&self) -> HassDomain {
+        HassDomain::from(self.domain_str())
+    }
+
+    /// The object id of the entity, e.g. `kitchen` for `light.kitchen`
+    pub fn object_id(&self) -> &str {
+        &self.0[self.0.find('.').map_or(0, |dot| dot + 1)..]
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub(crate) fn domain_str(&self) -> &str {
+        &self.0[..self.0.find('.').unwrap_or(0)]
+    }
+}
+
+/// lowercase letters, digits and underscores, without a leading, trailing or double underscore
+fn is_slug(part: &str) -> bool {
+    !part.is_empty()
+        && part
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'_')
+        && !part.starts_with('_')
+        && !part.ends_with('_')
+        && !part.contains("__")
+}
+
+impl FromStr for EntityId {
+    type Err = HassError;
+
+    fn from_str(entity_id: &str) -> HassResult<Self> {
+        Self::new(entity_id)
+    }
+}
+
+impl TryFrom<String> for EntityId {
+    type Error = HassError;
+
+    fn try_from(entity_id: String) -> HassResult<Self> {
+        Self::new(entity_id)
+    }
+}
+
+impl TryFrom<&str> for EntityId {
+    type Error = HassError;
+
+    fn try_from(entity_id: &str) -> HassResult<Self> {
+        Self::new(entity_id)
+    }
+}
+
+impl From<EntityId> for String {
+    fn from(entity_id: EntityId) -> Self {
+        entity_id.0
+    }
+}
+
+impl AsRef<str> for EntityId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for EntityId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for EntityId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for EntityId {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for EntityId {
+    fn eq(&self, other: &String) -> bool {
+        &self.0 == other
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for EntityId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for EntityId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entity_id = String::deserialize(deserializer)?;
+        Self::new(entity_id).map_err(serde::de::Error::custom)
+    }
+}
+
+/// This object represents the domain of an integration or of an entity
+///
+/// The domains not listed here, e.g. the ones of custom integrations, are kept as `Other`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub enum HassDomain {
+    AlarmControlPanel,
+    Automation,
+    BinarySensor,
+    Button,
+    Calendar,
+    Camera,
+    Climate,
+    Cover,
+    DeviceTracker,
+    Event,
+    Fan,
+    Group,
+    HomeAssistant,
+    Humidifier,
+    Image,
+    InputBoolean,
+    InputButton,
+    InputDatetime,
+    InputNumber,
+    InputSelect,
+    InputText,
+    LawnMower,
+    Light,
+    Lock,
+    MediaPlayer,
+    Notify,
+    Number,
+    Person,
+    Remote,
+    Scene,
+    Script,
+    Select,
+    Sensor,
+    Siren,
+    Sun,
+    Switch,
+    Text,
+    Timer,
+    Todo,
+    Tts,
+    Update,
+    Vacuum,
+    Valve,
+    WaterHeater,
+    Weather,
+    Zone,
+    Other(String),
+}
+
+/// the known domains, in the order of the HassDomain variants
+const DOMAINS: &[(&str, HassDomain)] = &[
+    ("alarm_control_panel", HassDomain::AlarmControlPanel),
+    ("automation", HassDomain::Automation),
+    ("binary_sensor", HassDomain::BinarySensor),
+    ("button", HassDomain::Button),
+    ("calendar", HassDomain::Calendar),
+    ("camera", HassDomain::Camera),
+    ("climate", HassDomain::Climate),
+    ("cover", HassDomain::Cover),
+    ("device_tracker", HassDomain::DeviceTracker),
+    ("event", HassDomain::Event),
+    ("fan", HassDomain::Fan),
+    ("group", HassDomain::Group),
+    ("homeassistant", HassDomain::HomeAssistant),
+    ("humidifier", HassDomain::Humidifier),
+    ("image", HassDomain::Image),
+    ("input_boolean", HassDomain::InputBoolean),
+    ("input_button", HassDomain::InputButton),
+    ("input_datetime", HassDomain::InputDatetime),
+    ("input_number", HassDomain::InputNumber),
+    ("input_select", HassDomain::InputSelect),
+    ("input_text", HassDomain::InputText),
+    ("lawn_mower", HassDomain::LawnMower),
+    ("light", HassDomain::Light),
+    ("lock", HassDomain::Lock),
+    ("media_player", HassDomain::MediaPlayer),
+    ("notify", HassDomain::Notify),
+    ("number", HassDomain::Number),
+    ("person", HassDomain::Person),
+    ("remote", HassDomain::Remote),
+    ("scene", HassDomain::Scene),
+    ("script", HassDomain::Script),
+    ("select", HassDomain::Select),
+    ("sensor", HassDomain::Sensor),
+    ("siren", HassDomain::Siren),
+    ("sun", HassDomain::Sun),
+    ("switch", HassDomain::Switch),
+    ("text", HassDomain::Text),
+    ("timer", HassDomain::Timer),
+    ("todo", HassDomain::Todo),
+    ("tts", HassDomain::Tts),
+    ("update", HassDomain::Update),
+    ("vacuum", HassDomain::Vacuum),
+    ("valve", HassDomain::Valve),
+    ("water_heater", HassDomain::WaterHeater),
+    ("weather", HassDomain::Weather),
+    ("zone", HassDomain::Zone),
+];
+
+impl HassDomain {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Other(domain) => domain,
+            known => DOMAINS
+                .iter()
+                .find(|(_, domain)| domain == known)
+                .map_or("", |(name, _)| name),
+        }
+    }
+}
+
+impl From<&str> for HassDomain {
+    fn from(domain: &str) -> Self {
+        DOMAINS
+            .iter()
+            .find(|(name, _)| *name == domain)
+            .map_or_else(
+                || Self::Other(domain.to_owned()),
+                |(_, known)| known.clone(),
+            )
+    }
+}
+
+impl From<String> for HassDomain {
+    fn from(domain: String) -> Self {
+        match Self::from(domain.as_str()) {
+            Self::Other(_) => Self::Other(domain),
+            known => known,
+        }
+    }
+}
+
+impl From<&String> for HassDomain {
+    fn from(domain: &String) -> Self {
+        Self::from(domain.as_str())
+    }
+}
+
+impl FromStr for HassDomain {
+    type Err = std::convert::Infallible;
+
+    fn from_str(domain: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(domain))
+    }
+}
+
+impl PartialEq<str> for HassDomain {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for HassDomain {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Display for HassDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for HassDomain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for HassDomain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::from(String::deserialize(deserializer)?))
+    }
+}
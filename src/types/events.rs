@@ -1,5 +1,5 @@
-use crate::types::{Context, HassEntity};
-use serde::{Deserialize, Serialize};
+use crate::types::{Context, EntityId, HassEntity};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use std::{collections::HashMap, fmt};
 
@@ -19,28 +19,48 @@ pub struct HassEvent {
     pub context: Context,
 }
 
+#[cfg(feature = "chrono")]
+impl HassEvent {
+    /// The moment the event was fired, `None` if the timestamp is malformed
+    pub fn time_fired_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::parse_iso(&self.time_fired)
+    }
+}
+
 /// This is part of HassEvent
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct EventData {
-    pub entity_id: Option<String>,
+    /// `None` as well when the `entity_id` of a custom event is not a valid entity id
+    #[serde(default, deserialize_with = "lenient_entity_id")]
+    pub entity_id: Option<EntityId>,
     pub new_state: Option<HassEntity>,
     pub old_state: Option<HassEntity>,
     #[serde(flatten)]
     pub extra: HashMap<String, Value>,
 }
 
+/// any event can be fired with any data, so a malformed entity id must not fail the whole event
+fn lenient_entity_id<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<EntityId>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(entity_id) => EntityId::new(entity_id).ok(),
+        _ => None,
+    })
+}
+
 impl fmt::Display for HassEvent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "HassEvent {{\n")?;
-        write!(f, "  event_type: {},\n", self.event_type)?;
-        write!(f, "  data: {{\n")?;
-        write!(f, "    entity_id: {:?},\n", self.data.entity_id)?;
-        write!(f, "    new_state: {:?},\n", self.data.new_state)?;
-        write!(f, "    old_state: {:?},\n", self.data.old_state)?;
-        write!(f, "  }},\n")?;
-        write!(f, "  origin: {},\n", self.origin)?;
-        write!(f, "  time_fired: {},\n", self.time_fired)?;
-        write!(f, "  context: {:?},\n", self.context)?;
+        writeln!(f, "HassEvent {{")?;
+        writeln!(f, "  event_type: {},", self.event_type)?;
+        writeln!(f, "  data: {{")?;
+        writeln!(f, "    entity_id: {:?},", self.data.entity_id)?;
+        writeln!(f, "    new_state: {:?},", self.data.new_state)?;
+        writeln!(f, "    old_state: {:?},", self.data.old_state)?;
+        writeln!(f, "  }},")?;
+        writeln!(f, "  origin: {},", self.origin)?;
+        writeln!(f, "  time_fired: {},", self.time_fired)?;
+        writeln!(f, "  context: {:?},", self.context)?;
         write!(f, "}}")?;
         Ok(())
     }
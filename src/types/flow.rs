@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// This object represents the current step of a Home Assistant data entry flow
+///
+/// Data entry flows are used to set up an integration (config flow), to change its options
+/// (options flow) and to fix a repair issue (repair flow).
+/// [Data Entry Flow](https://developers.home-assistant.io/docs/data_entry_flow_index)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FlowStep {
+    /// The user has to fill in a form and submit it
+    Form(FlowForm),
+    /// The user has to pick the next step
+    Menu(FlowMenu),
+    /// The user has to complete the step on an external website, e.g. an OAuth2 authorization
+    External(FlowExternal),
+    /// The external step was completed, the flow can be continued
+    ExternalDone(FlowInfo),
+    /// A long running task is in progress, the flow can be continued once done
+    Progress(FlowProgress),
+    /// The long running task is done, the flow can be continued
+    ProgressDone(FlowInfo),
+    /// The flow finished successfully
+    CreateEntry(FlowCreateEntry),
+    /// The flow was aborted by the integration
+    Abort(FlowAbort),
+}
+
+impl FlowStep {
+    /// The id of the flow, used to submit the next step
+    pub fn flow_id(&self) -> &str {
+        match self {
+            Self::Form(step) => &step.flow_id,
+            Self::Menu(step) => &step.flow_id,
+            Self::External(step) => &step.flow_id,
+            Self::ExternalDone(step) | Self::ProgressDone(step) => &step.flow_id,
+            Self::Progress(step) => &step.flow_id,
+            Self::CreateEntry(step) => &step.flow_id,
+            Self::Abort(step) => &step.flow_id,
+        }
+    }
+
+    /// Returns true if the flow is finished, either successfully or aborted
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::CreateEntry(_) | Self::Abort(_))
+    }
+}
+
+/// This is part of FlowStep
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowInfo {
+    pub flow_id: String,
+    pub handler: String,
+    pub step_id: Option<String>,
+}
+
+/// This is part of FlowStep
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowForm {
+    pub flow_id: String,
+    pub handler: String,
+    pub step_id: String,
+    #[serde(default)]
+    pub data_schema: Vec<FlowSchemaField>,
+    /// the errors of the previously submitted input, keyed by field name or `base`
+    #[serde(default)]
+    pub errors: Option<HashMap<String, String>>,
+    pub description_placeholders: Option<HashMap<String, Value>>,
+    pub last_step: Option<bool>,
+    pub preview: Option<String>,
+}
+
+/// A field of the form, as serialized by `voluptuous_serialize`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowSchemaField {
+    pub name: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub optional: bool,
+    #[serde(rename = "type")]
+    pub field_type: Option<String>,
+    pub default: Option<Value>,
+    pub selector: Option<Value>,
+    /// the remaining keys, e.g. `valueMin` or `options`
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of FlowStep
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowMenu {
+    pub flow_id: String,
+    pub handler: String,
+    pub step_id: String,
+    /// the next step ids, either as a list or as a map of step id to label
+    pub menu_options: Value,
+    pub description_placeholders: Option<HashMap<String, Value>>,
+}
+
+/// This is part of FlowStep
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowExternal {
+    pub flow_id: String,
+    pub handler: String,
+    pub step_id: String,
+    pub url: String,
+    pub description_placeholders: Option<HashMap<String, Value>>,
+}
+
+/// This is part of FlowStep
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowProgress {
+    pub flow_id: String,
+    pub handler: String,
+    pub step_id: String,
+    pub progress_action: String,
+    pub description_placeholders: Option<HashMap<String, Value>>,
+}
+
+/// This is part of FlowStep
+///
+/// For a config flow `result` holds the created config entry.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowCreateEntry {
+    pub flow_id: String,
+    pub handler: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub description_placeholders: Option<HashMap<String, Value>>,
+    pub result: Option<Value>,
+}
+
+/// This is part of FlowStep
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowAbort {
+    pub flow_id: String,
+    pub handler: String,
+    pub reason: String,
+    pub description_placeholders: Option<HashMap<String, Value>>,
+}
+
+/// This object represents a config flow in progress, as returned by `config_entries/flow/progress`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FlowInProgress {
+    pub flow_id: String,
+    pub handler: String,
+    pub step_id: Option<String>,
+    pub context: HashMap<String, Value>,
+}
@@ -19,7 +19,49 @@ pub struct HassIssue {
     pub translation_placeholders: Option<HashMap<String, Value>>,
 }
 
+#[cfg(feature = "chrono")]
+impl HassIssue {
+    /// The moment the issue was created, `None` if the timestamp is malformed
+    pub fn created_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::parse_iso(&self.created)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct HassIssues {
     pub issues: Vec<HassIssue>,
 }
+
+/// This is the event fired by Home Assistant when a repair issue is created, updated or removed
+///
+/// received on the `repairs_issue_registry_updated` event subscription
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct HassIssueEvent {
+    pub data: HassIssueEventData,
+    pub time_fired: String,
+}
+
+#[cfg(feature = "chrono")]
+impl HassIssueEvent {
+    /// The moment the event was fired, `None` if the timestamp is malformed
+    pub fn time_fired_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::parse_iso(&self.time_fired)
+    }
+}
+
+/// This is part of HassIssueEvent
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct HassIssueEventData {
+    pub action: HassIssueAction,
+    pub domain: String,
+    pub issue_id: String,
+}
+
+/// This is part of HassIssueEvent
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HassIssueAction {
+    Create,
+    Update,
+    Remove,
+}
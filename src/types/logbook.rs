@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// This object represents a Home Assistant Logbook entry
+///
+/// returned by `logbook/get_events` and streamed by `logbook/event_stream`.
+/// The `context_*` fields describe who or what caused the change, e.g. the user, the
+/// automation or the service call.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LogbookEntry {
+    /// seconds since the UNIX epoch
+    pub when: f64,
+    pub name: Option<String>,
+    pub message: Option<String>,
+    pub domain: Option<String>,
+    pub entity_id: Option<String>,
+    pub state: Option<String>,
+    pub icon: Option<String>,
+    pub source: Option<String>,
+    pub context_id: Option<String>,
+    pub context_user_id: Option<String>,
+    pub context_event_type: Option<String>,
+    pub context_domain: Option<String>,
+    pub context_service: Option<String>,
+    pub context_entity_id: Option<String>,
+    pub context_entity_id_name: Option<String>,
+    pub context_name: Option<String>,
+    pub context_message: Option<String>,
+    pub context_state: Option<String>,
+}
+
+#[cfg(feature = "chrono")]
+impl LogbookEntry {
+    /// The moment the entry was recorded
+    pub fn when_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.when)
+    }
+}
+
+/// This is a message received on the `logbook/event_stream` subscription
+///
+/// The first messages carry the historical entries together with the `start_time` and `end_time`
+/// of the requested window, `partial` being set while more historical entries are to come.
+/// Live entries are sent afterwards without the time boundaries.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LogbookStreamMessage {
+    pub events: Vec<LogbookEntry>,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    pub partial: Option<bool>,
+}
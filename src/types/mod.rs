@@ -1,25 +1,49 @@
 //! API types.
 
+mod calendar;
 mod command;
 mod config;
+mod config_entries;
 mod entities;
+mod entity_id;
 mod events;
+mod flow;
 mod issue;
+mod logbook;
 mod panels;
-mod response;
-mod services;
 mod registry_area;
 mod registry_device;
 mod registry_entity;
+mod registry_floor;
+mod registry_label;
+mod response;
+mod selector;
+mod server_info;
+mod services;
+mod states;
+mod statistics;
+#[cfg(feature = "chrono")]
+pub(crate) mod timestamp;
 
+pub use calendar::*;
 pub(crate) use command::*;
 pub use config::*;
+pub use config_entries::*;
 pub use entities::*;
+pub use entity_id::*;
 pub use events::*;
+pub use flow::*;
 pub use issue::*;
+pub use logbook::*;
 pub use panels::*;
-pub use response::*;
-pub use services::*;
 pub use registry_area::*;
 pub use registry_device::*;
 pub use registry_entity::*;
+pub use registry_floor::*;
+pub use registry_label::*;
+pub use response::*;
+pub use selector::*;
+pub use server_info::*;
+pub use services::*;
+pub use states::*;
+pub use statistics::*;
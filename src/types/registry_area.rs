@@ -14,3 +14,16 @@ pub struct HassRegistryArea {
     pub created_at: f64,
     pub modified_at: f64,
 }
+
+#[cfg(feature = "chrono")]
+impl HassRegistryArea {
+    /// The moment the registry entry was created
+    pub fn created_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.created_at)
+    }
+
+    /// The moment the registry entry was last modified
+    pub fn modified_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.modified_at)
+    }
+}
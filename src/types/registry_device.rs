@@ -27,4 +27,17 @@ pub struct HassRegistryDevice {
     pub serial_number: Option<String>,
     pub sw_version: Option<String>,
     pub via_device_id: Option<String>,
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "chrono")]
+impl HassRegistryDevice {
+    /// The moment the registry entry was created
+    pub fn created_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.created_at)
+    }
+
+    /// The moment the registry entry was last modified
+    pub fn modified_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.modified_at)
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crate::types::EntityId;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -13,7 +14,7 @@ pub struct HassRegistryEntity {
     pub device_id: Option<String>,
     pub disabled_by: Option<String>,
     pub entity_category: Option<String>,
-    pub entity_id: String,
+    pub entity_id: EntityId,
     pub has_entity_name: bool,
     pub hidden_by: Option<String>,
     pub icon: Option<String>,
@@ -26,4 +27,17 @@ pub struct HassRegistryEntity {
     pub platform: String,
     pub translation_key: Option<String>,
     pub unique_id: String,
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "chrono")]
+impl HassRegistryEntity {
+    /// The moment the registry entry was created
+    pub fn created_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.created_at)
+    }
+
+    /// The moment the registry entry was last modified
+    pub fn modified_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.modified_at)
+    }
+}
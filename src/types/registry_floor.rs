@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct HassRegistryFloor {
+    pub aliases: Vec<String>,
+    pub floor_id: String,
+    pub icon: Option<String>,
+    pub level: Option<i32>,
+    pub name: String,
+    pub created_at: f64,
+    pub modified_at: f64,
+}
+
+#[cfg(feature = "chrono")]
+impl HassRegistryFloor {
+    /// The moment the registry entry was created
+    pub fn created_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.created_at)
+    }
+
+    /// The moment the registry entry was last modified
+    pub fn modified_at_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.modified_at)
+    }
+}
@@ -1,11 +1,13 @@
 use crate::types::HassEvent;
 use crate::HassResult;
 
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde_json::Value;
 
 ///The tag identifying which variant we are dealing with is inside of the content,
 /// next to any other fields of the variant.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Response {
@@ -65,7 +67,7 @@ pub struct WSPong {
     pub id: u64,
 }
 
-///	This object represents the Home Assistant Event
+/// This object represents the Home Assistant Event
 ///
 /// received when the client is subscribed to
 /// [Subscribe to events](https://developers.home-assistant.io/docs/api/websocket/#subscribe-to-events)
@@ -96,6 +98,7 @@ impl WSResult {
         !self.success
     }
 
+    #[allow(clippy::result_large_err)]
     pub fn result(self) -> HassResult<Value> {
         if self.success {
             if let Some(result) = self.result {
@@ -111,3 +114,28 @@ pub struct ErrorCode {
     pub code: String,
     pub message: String,
 }
+
+/// A typed response together with the raw JSON result it was decoded from
+///
+/// The raw value keeps the fields not modeled by the typed struct, and is still available when
+/// the typed decoding fails, e.g. when a newer Home Assistant version removed a required field.
+#[derive(Debug)]
+pub struct WithRaw<T> {
+    pub raw: Value,
+    pub typed: Result<T, serde_json::Error>,
+}
+
+impl<T: DeserializeOwned> WithRaw<T> {
+    pub(crate) fn new(raw: Value) -> Self {
+        let typed = T::deserialize(&raw);
+        Self { raw, typed }
+    }
+}
+
+impl<T> WithRaw<T> {
+    /// Returns the typed value, or `HassError::UnableToDeserialize` if the decoding failed
+    #[allow(clippy::result_large_err)]
+    pub fn into_typed(self) -> HassResult<T> {
+        Ok(self.typed?)
+    }
+}
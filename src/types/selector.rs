@@ -0,0 +1,356 @@
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// This object represents the selector of a service field, describing the expected value
+///
+/// It is serialized as a single entry map, e.g. `{"number": {"min": 0, "max": 100}}`.
+/// [Selectors](https://www.home-assistant.io/docs/blueprint/selectors/)
+#[derive(Debug, PartialEq, Clone)]
+pub enum Selector {
+    Action,
+    Area(AreaSelector),
+    Boolean,
+    ColorRgb,
+    ColorTemp(ColorTempSelector),
+    Constant(ConstantSelector),
+    Date,
+    Datetime,
+    Device(DeviceSelector),
+    Duration(DurationSelector),
+    Entity(EntitySelector),
+    Icon,
+    Number(NumberSelector),
+    Object,
+    Select(SelectSelector),
+    Target(TargetSelector),
+    Template,
+    Text(TextSelector),
+    Time,
+    /// A selector not modeled by this library, e.g. `media` or `config_entry`
+    Other {
+        kind: String,
+        config: Value,
+    },
+}
+
+impl Selector {
+    /// The selector name, e.g. `number`
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::Action => "action",
+            Self::Area(_) => "area",
+            Self::Boolean => "boolean",
+            Self::ColorRgb => "color_rgb",
+            Self::ColorTemp(_) => "color_temp",
+            Self::Constant(_) => "constant",
+            Self::Date => "date",
+            Self::Datetime => "datetime",
+            Self::Device(_) => "device",
+            Self::Duration(_) => "duration",
+            Self::Entity(_) => "entity",
+            Self::Icon => "icon",
+            Self::Number(_) => "number",
+            Self::Object => "object",
+            Self::Select(_) => "select",
+            Self::Target(_) => "target",
+            Self::Template => "template",
+            Self::Text(_) => "text",
+            Self::Time => "time",
+            Self::Other { kind, .. } => kind,
+        }
+    }
+
+    /// Returns true if the selector accepts a list of values
+    pub fn is_multiple(&self) -> bool {
+        match self {
+            Self::Area(selector) => selector.multiple,
+            Self::Device(selector) => selector.multiple,
+            Self::Entity(selector) => selector.multiple,
+            Self::Select(selector) => selector.multiple,
+            Self::Text(selector) => selector.multiple,
+            Self::Other { config, .. } => config["multiple"].as_bool().unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    fn config(&self) -> Result<Value, serde_json::Error> {
+        match self {
+            Self::Area(config) => serde_json::to_value(config),
+            Self::ColorTemp(config) => serde_json::to_value(config),
+            Self::Constant(config) => serde_json::to_value(config),
+            Self::Device(config) => serde_json::to_value(config),
+            Self::Duration(config) => serde_json::to_value(config),
+            Self::Entity(config) => serde_json::to_value(config),
+            Self::Number(config) => serde_json::to_value(config),
+            Self::Select(config) => serde_json::to_value(config),
+            Self::Target(config) => serde_json::to_value(config),
+            Self::Text(config) => serde_json::to_value(config),
+            Self::Other { config, .. } => Ok(config.clone()),
+            _ => Ok(Value::Object(Default::default())),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Selector {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = serde_json::Map::<String, Value>::deserialize(deserializer)?;
+        let mut entries = map.into_iter();
+        let (kind, config) = match (entries.next(), entries.next()) {
+            (Some(entry), None) => entry,
+            _ => return Err(de::Error::custom("a selector has exactly one entry")),
+        };
+
+        // the config is null for selectors without options, e.g. `{"text": null}`
+        fn parse<T: DeserializeOwned + Default, E: de::Error>(config: Value) -> Result<T, E> {
+            match config {
+                Value::Null => Ok(T::default()),
+                config => serde_json::from_value(config).map_err(E::custom),
+            }
+        }
+
+        Ok(match kind.as_str() {
+            "action" => Self::Action,
+            "area" => Self::Area(parse(config)?),
+            "boolean" => Self::Boolean,
+            "color_rgb" => Self::ColorRgb,
+            "color_temp" => Self::ColorTemp(parse(config)?),
+            "constant" => Self::Constant(parse(config)?),
+            "date" => Self::Date,
+            "datetime" => Self::Datetime,
+            "device" => Self::Device(parse(config)?),
+            "duration" => Self::Duration(parse(config)?),
+            "entity" => Self::Entity(parse(config)?),
+            "icon" => Self::Icon,
+            "number" => Self::Number(parse(config)?),
+            "object" => Self::Object,
+            "select" => Self::Select(parse(config)?),
+            "target" => Self::Target(parse(config)?),
+            "template" => Self::Template,
+            "text" => Self::Text(parse(config)?),
+            "time" => Self::Time,
+            _ => Self::Other { kind, config },
+        })
+    }
+}
+
+impl Serialize for Selector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let config = self.config().map_err(serde::ser::Error::custom)?;
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.kind(), &config)?;
+        map.end()
+    }
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct NumberSelector {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// a number, or `any`
+    pub step: Option<Value>,
+    pub unit_of_measurement: Option<String>,
+    /// `box` or `slider`
+    pub mode: Option<String>,
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SelectSelector {
+    #[serde(default)]
+    pub options: Vec<SelectOption>,
+    #[serde(default)]
+    pub multiple: bool,
+    #[serde(default)]
+    pub custom_value: bool,
+    pub mode: Option<String>,
+    pub translation_key: Option<String>,
+}
+
+impl SelectSelector {
+    /// Returns true if the value is one of the options, or if custom values are allowed
+    pub fn accepts(&self, value: &str) -> bool {
+        self.custom_value || self.options.iter().any(|option| option.value() == value)
+    }
+}
+
+/// This is part of SelectSelector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum SelectOption {
+    Value(String),
+    Labeled { value: String, label: String },
+}
+
+impl SelectOption {
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Value(value) | Self::Labeled { value, .. } => value,
+        }
+    }
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct EntitySelector {
+    #[serde(default)]
+    pub multiple: bool,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub filter: Vec<EntityFilter>,
+    #[serde(default)]
+    pub include_entities: Vec<String>,
+    #[serde(default)]
+    pub exclude_entities: Vec<String>,
+    /// the filter of older services, set next to the other options
+    #[serde(flatten)]
+    pub legacy_filter: EntityFilter,
+}
+
+impl EntitySelector {
+    /// The filters of the selector, the legacy filter included if set
+    ///
+    /// An entity is accepted if it matches any of them, or if there is none.
+    pub fn filters(&self) -> impl Iterator<Item = &EntityFilter> {
+        self.filter
+            .iter()
+            .chain((!self.legacy_filter.is_empty()).then_some(&self.legacy_filter))
+    }
+
+    /// The domains the entity has to belong to, empty if any domain is accepted
+    pub fn domains(&self) -> Vec<&str> {
+        if self.filters().any(|filter| filter.domain.is_empty()) {
+            return Vec::new();
+        }
+        self.filters()
+            .flat_map(|filter| filter.domain.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+/// This is part of EntitySelector, DeviceSelector and TargetSelector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct EntityFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integration: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub domain: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub device_class: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supported_features: Option<Value>,
+}
+
+impl EntityFilter {
+    /// Whether the filter accepts every entity
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// This is part of DeviceSelector, AreaSelector and TargetSelector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct DeviceFilter {
+    pub integration: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct DeviceSelector {
+    #[serde(default)]
+    pub multiple: bool,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub filter: Vec<DeviceFilter>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub entity: Vec<EntityFilter>,
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct AreaSelector {
+    #[serde(default)]
+    pub multiple: bool,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub entity: Vec<EntityFilter>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub device: Vec<DeviceFilter>,
+}
+
+/// This is part of Selector, it is also the `target` specification of a service
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct TargetSelector {
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub entity: Vec<EntityFilter>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub device: Vec<DeviceFilter>,
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct TextSelector {
+    #[serde(default)]
+    pub multiline: bool,
+    #[serde(default)]
+    pub multiple: bool,
+    /// the input type, e.g. `password` or `url`
+    #[serde(rename = "type")]
+    pub text_type: Option<String>,
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct ColorTempSelector {
+    /// `kelvin` or `mired`
+    pub unit: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub min_mireds: Option<f64>,
+    pub max_mireds: Option<f64>,
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct ConstantSelector {
+    pub value: Value,
+    pub label: Option<String>,
+}
+
+/// This is part of Selector
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct DurationSelector {
+    #[serde(default)]
+    pub enable_day: bool,
+}
+
+/// accepts a single value or a list of values
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        Many(Vec<T>),
+        One(T),
+    }
+
+    match Option::<OneOrMany<T>>::deserialize(deserializer)? {
+        Some(OneOrMany::Many(values)) => Ok(values),
+        Some(OneOrMany::One(value)) => Ok(vec![value]),
+        None => Ok(Vec::new()),
+    }
+}
@@ -0,0 +1,126 @@
+use crate::HassError;
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A Home Assistant release, e.g. `2024.6.0` or the beta `2024.6.0b1`
+///
+/// The versions compare in release order, a pre-release comes before its release.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HaVersion {
+    /// the year of the release, `0` for the releases before 2021
+    pub major: u32,
+    /// the month of the release
+    pub minor: u32,
+    pub patch: u32,
+    /// the pre-release suffix, e.g. `b1` or `.dev20240501`
+    pub pre: Option<String>,
+}
+
+impl HaVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+}
+
+impl Ord for HaVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(pre), Some(other)) => pre_release(pre)
+                    .cmp(&pre_release(other))
+                    .then_with(|| pre.cmp(other)),
+            })
+    }
+}
+
+/// The order of a pre-release suffix, its kind then its number, so that `b10` comes after `b9`
+/// and the `.dev` builds come before the alphas, the betas and the release candidates
+fn pre_release(pre: &str) -> (u8, &str, u64) {
+    let pre = pre.trim_start_matches(['.', '-']);
+    let digits = pre
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |last| last + 1);
+    let (kind, number) = pre.split_at(digits);
+    let rank = match kind {
+        "dev" => 0,
+        "a" => 1,
+        "b" => 2,
+        "rc" => 3,
+        _ => 4,
+    };
+    (rank, kind, number.parse().unwrap_or(0))
+}
+
+impl PartialOrd for HaVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for HaVersion {
+    type Err = HassError;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let invalid = || HassError::InvalidVersion(version.to_owned());
+
+        let mut parts = version.splitn(3, '.');
+        let major = parts.next().and_then(|part| part.parse().ok());
+        let minor = parts.next().and_then(|part| part.parse().ok());
+        let (Some(major), Some(minor)) = (major, minor) else {
+            return Err(invalid());
+        };
+
+        // the patch number is followed by the pre-release suffix, if any
+        let rest = parts.next().unwrap_or("0");
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let patch = rest[..digits].parse().map_err(|_| invalid())?;
+        let pre = (digits < rest.len()).then(|| rest[digits..].to_owned());
+
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+}
+
+impl fmt::Display for HaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        match &self.pre {
+            Some(pre) => f.write_str(pre),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What the client knows about the server it is connected to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub ha_version: HaVersion,
+}
+
+impl ServerInfo {
+    /// Whether the server runs `required` or a later release
+    ///
+    /// The betas of `required` already ship its features, so the pre-release suffix is ignored.
+    pub fn supports(&self, required: &HaVersion) -> bool {
+        let version = &self.ha_version;
+        (version.major, version.minor, version.patch)
+            >= (required.major, required.minor, required.patch)
+    }
+}
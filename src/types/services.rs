@@ -1,3 +1,4 @@
+use crate::types::{Selector, TargetSelector};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -7,7 +8,7 @@ use std::fmt;
 ///
 /// This will get a dump of the current services in Home Assistant.
 /// [Fetch Services](https://developers.home-assistant.io/docs/api/websocket/#fetching-services)
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct HassServices(pub Domain);
 
 /// This is part of HassServices
@@ -20,49 +21,221 @@ pub type ServiceName = HashMap<String, HassService>;
 ///
 /// This will get a dump of the current services in Home Assistant.
 /// [Fetch Services](https://developers.home-assistant.io/docs/api/websocket/#fetching-services)
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(try_from = "RawHassService", into = "RawHassService")]
 pub struct HassService {
     pub name: Option<String>,
     pub description: Option<String>,
+    /// the fields outside of any section
     pub fields: FieldName,
-    //pub response: Option<bool>,
+    /// the collapsible groups of fields, keyed by section name
+    pub sections: HashMap<String, FieldSection>,
+    /// the entities, devices and areas the service can be applied to, `None` if it takes no target
+    pub target: Option<TargetSelector>,
+    /// set if the service can return response data
+    pub response: Option<ServiceResponse>,
+}
+
+impl HassService {
+    /// All the fields of the service, including the ones grouped in sections
+    pub fn all_fields(&self) -> impl Iterator<Item = (&String, &Field)> {
+        self.fields
+            .iter()
+            .chain(self.sections.values().flat_map(|section| &section.fields))
+    }
+
+    /// Returns true if the service has to be called with `return_response`
+    pub fn requires_response(&self) -> bool {
+        self.response
+            .as_ref()
+            .is_some_and(|response| !response.optional)
+    }
+}
+
+/// This is part of HassService
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct FieldSection {
+    #[serde(default)]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub fields: FieldName,
+}
+
+/// This is part of HassService
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub struct ServiceResponse {
+    /// when false the service always returns response data
+    pub optional: bool,
+}
+
+/// The service as serialized by Home Assistant, the sections being mixed with the fields
+#[derive(Serialize, Deserialize)]
+struct RawHassService {
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    fields: HashMap<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<TargetSelector>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<ServiceResponse>,
+}
+
+impl TryFrom<RawHassService> for HassService {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawHassService) -> Result<Self, Self::Error> {
+        let mut fields = HashMap::new();
+        let mut sections = HashMap::new();
+        for (name, value) in raw.fields {
+            // a section is the only entry with nested fields
+            if value.get("fields").is_some_and(Value::is_object) {
+                sections.insert(name, serde_json::from_value(value)?);
+            } else {
+                fields.insert(name, serde_json::from_value(value)?);
+            }
+        }
+
+        Ok(Self {
+            name: raw.name,
+            description: raw.description,
+            fields,
+            sections,
+            target: raw.target,
+            response: raw.response,
+        })
+    }
+}
+
+impl From<HassService> for RawHassService {
+    fn from(service: HassService) -> Self {
+        let fields =
+            service
+                .fields
+                .into_iter()
+                .filter_map(|(name, field)| Some((name, serde_json::to_value(field).ok()?)))
+                .chain(service.sections.into_iter().filter_map(|(name, section)| {
+                    Some((name, serde_json::to_value(section).ok()?))
+                }))
+                .collect();
+
+        Self {
+            name: service.name,
+            description: service.description,
+            fields,
+            target: service.target,
+            response: service.response,
+        }
+    }
+}
+
+/// The entities, devices, areas, floors or labels a service call is applied to
+///
+/// [Service call target](https://www.home-assistant.io/docs/scripts/perform-actions/#targeting-areas-and-devices)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct Target {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entity_id: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_id: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub area_id: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub floor_id: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub label_id: Vec<String>,
+}
+
+impl Target {
+    /// Targets a single entity
+    pub fn entity(entity_id: &str) -> Self {
+        Self {
+            entity_id: vec![entity_id.to_owned()],
+            ..Default::default()
+        }
+    }
+
+    /// Targets a single device
+    pub fn device(device_id: &str) -> Self {
+        Self {
+            device_id: vec![device_id.to_owned()],
+            ..Default::default()
+        }
+    }
+
+    /// Targets all the entities of an area
+    pub fn area(area_id: &str) -> Self {
+        Self {
+            area_id: vec![area_id.to_owned()],
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entity_id.is_empty()
+            && self.device_id.is_empty()
+            && self.area_id.is_empty()
+            && self.floor_id.is_empty()
+            && self.label_id.is_empty()
+    }
 }
 
 /// This is part of HassService
 pub type FieldName = HashMap<String, Field>;
 
 ///This is part of HassService
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Field {
     pub name: Option<String>,
     pub description: Option<String>,
     pub example: Option<Value>,
+    #[serde(default)]
+    pub required: bool,
+    /// the value used by Home Assistant when the field is omitted
+    pub default: Option<Value>,
+    /// set if the field is only shown in advanced mode
+    #[serde(default)]
+    pub advanced: bool,
+    /// the field is only available for the entities matching the filter
+    pub filter: Option<FieldFilter>,
+    pub selector: Option<Selector>,
+}
+
+/// This is part of Field
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct FieldFilter {
+    /// the feature flags, one of them has to be supported by the entity
+    #[serde(default)]
+    pub supported_features: Vec<u32>,
+    /// the attribute values, keyed by attribute name, one of them has to be set on the entity
+    #[serde(default)]
+    pub attribute: HashMap<String, Vec<Value>>,
 }
 
 impl fmt::Display for HassServices {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "HassServices {{\n")?;
-        write!(f, "  domain: {{\n")?;
+        writeln!(f, "HassServices {{")?;
+        writeln!(f, "  domain: {{")?;
         for (domain_name, service_name) in &self.0 {
-            write!(f, "    {}: {{\n", domain_name)?;
+            writeln!(f, "    {}: {{", domain_name)?;
             for (service_name, hass_service) in service_name {
-                write!(f, "      {}: {{\n", service_name)?;
-                write!(f, "        name: {:?},\n", hass_service.name)?;
-                write!(f, "        description: {:?},\n", hass_service.description)?;
-                write!(f, "        fields: {{\n")?;
-                for (field_name, field) in &hass_service.fields {
-                    write!(f, "          {}: {{\n", field_name)?;
-                    write!(f, "            name: {:?},\n", field.name)?;
-                    write!(f, "            description: {:?},\n", field.description)?;
-                    write!(f, "            example: {:?},\n", field.example)?;
-                    write!(f, "          }},\n")?;
+                writeln!(f, "      {}: {{", service_name)?;
+                writeln!(f, "        name: {:?},", hass_service.name)?;
+                writeln!(f, "        description: {:?},", hass_service.description)?;
+                writeln!(f, "        fields: {{")?;
+                for (field_name, field) in hass_service.all_fields() {
+                    writeln!(f, "          {}: {{", field_name)?;
+                    writeln!(f, "            name: {:?},", field.name)?;
+                    writeln!(f, "            description: {:?},", field.description)?;
+                    writeln!(f, "            example: {:?},", field.example)?;
+                    writeln!(f, "          }},")?;
                 }
-                write!(f, "        }},\n")?;
-                write!(f, "      }},\n")?;
+                writeln!(f, "        }},")?;
+                writeln!(f, "      }},")?;
             }
-            write!(f, "    }},\n")?;
+            writeln!(f, "    }},")?;
         }
-        write!(f, "  }},\n")?;
+        writeln!(f, "  }},")?;
         write!(f, "}}")?;
         Ok(())
     }
@@ -70,15 +243,15 @@ impl fmt::Display for HassServices {
 
 impl fmt::Display for HassService {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "    name: {:?},\n", self.name)?;
-        write!(f, "    description: {:?},\n", self.description)?;
-        write!(f, "    fields: {{\n")?;
-        for (field_name, field) in &self.fields {
-            write!(f, "      {}: {{\n", field_name)?;
-            write!(f, "          name: {:?},\n", field.name)?;
-            write!(f, "          description: {:?},\n", field.description)?;
-            write!(f, "          example: {:?},\n", field.example)?;
-            write!(f, "          }},\n")?;
+        writeln!(f, "    name: {:?},", self.name)?;
+        writeln!(f, "    description: {:?},", self.description)?;
+        writeln!(f, "    fields: {{")?;
+        for (field_name, field) in self.all_fields() {
+            writeln!(f, "      {}: {{", field_name)?;
+            writeln!(f, "          name: {:?},", field.name)?;
+            writeln!(f, "          description: {:?},", field.description)?;
+            writeln!(f, "          example: {:?},", field.example)?;
+            writeln!(f, "          }},")?;
         }
         Ok(())
     }
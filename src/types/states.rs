@@ -0,0 +1,403 @@
+use crate::types::{EntityId, HassEntity};
+use crate::{HassError, HassResult};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The state of an entity, with the special states shared by all the domains modeled explicitly
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum StateValue<T> {
+    /// The entity is not reachable, e.g. the device is offline
+    Unavailable,
+    /// The state of the entity is not known yet
+    Unknown,
+    Value(T),
+    /// A state not modeled by this library
+    Other(String),
+}
+
+impl<T: DeserializeOwned> StateValue<T> {
+    /// Parses a raw state, falling back to `Other` if it does not match `T`
+    pub fn parse(state: &str) -> Self {
+        match state {
+            "unavailable" => Self::Unavailable,
+            "unknown" => Self::Unknown,
+            // numeric states, e.g. of sensors, are sent as strings too
+            _ => serde_json::from_value(Value::String(state.to_owned()))
+                .ok()
+                .or_else(|| {
+                    let number: f64 = state.parse().ok()?;
+                    serde_json::from_value(Value::from(number)).ok()
+                })
+                .map_or_else(|| Self::Other(state.to_owned()), Self::Value),
+        }
+    }
+}
+
+impl<T> StateValue<T> {
+    /// Returns the value, if the state is a known one
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the entity is unavailable
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, Self::Unavailable)
+    }
+}
+
+/// A typed view of a HassEntity, created with `HassEntity::as_light`, `HassEntity::as_climate`, ...
+///
+/// The attributes not modeled by `A` are kept in its `extra` map.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TypedEntity<S, A> {
+    pub entity_id: EntityId,
+    pub state: StateValue<S>,
+    pub attributes: A,
+}
+
+pub type LightState = TypedEntity<OnOff, LightAttributes>;
+pub type ClimateState = TypedEntity<HvacMode, ClimateAttributes>;
+pub type CoverState = TypedEntity<CoverStatus, CoverAttributes>;
+pub type SensorState = TypedEntity<f64, SensorAttributes>;
+pub type BinarySensorState = TypedEntity<OnOff, BinarySensorAttributes>;
+pub type MediaPlayerState = TypedEntity<MediaPlayerStatus, MediaPlayerAttributes>;
+pub type LockState = TypedEntity<LockStatus, LockAttributes>;
+pub type FanState = TypedEntity<OnOff, FanAttributes>;
+pub type VacuumState = TypedEntity<VacuumStatus, VacuumAttributes>;
+pub type WeatherState = TypedEntity<WeatherCondition, WeatherAttributes>;
+
+#[allow(clippy::result_large_err)]
+impl HassEntity {
+    /// Creates a typed view of the entity, provided it belongs to `domain`
+    pub fn typed<S, A>(&self, domain: &str) -> HassResult<TypedEntity<S, A>>
+    where
+        S: DeserializeOwned,
+        A: DeserializeOwned,
+    {
+        if self.entity_id.domain_str() != domain {
+            return Err(HassError::UnexpectedDomain {
+                expected: domain.to_owned(),
+                entity_id: self.entity_id.to_string(),
+            });
+        }
+
+        Ok(TypedEntity {
+            entity_id: self.entity_id.clone(),
+            state: StateValue::parse(&self.state),
+            attributes: serde_json::from_value(self.attributes.clone())?,
+        })
+    }
+
+    pub fn as_light(&self) -> HassResult<LightState> {
+        self.typed("light")
+    }
+
+    pub fn as_climate(&self) -> HassResult<ClimateState> {
+        self.typed("climate")
+    }
+
+    pub fn as_cover(&self) -> HassResult<CoverState> {
+        self.typed("cover")
+    }
+
+    pub fn as_sensor(&self) -> HassResult<SensorState> {
+        self.typed("sensor")
+    }
+
+    pub fn as_binary_sensor(&self) -> HassResult<BinarySensorState> {
+        self.typed("binary_sensor")
+    }
+
+    pub fn as_media_player(&self) -> HassResult<MediaPlayerState> {
+        self.typed("media_player")
+    }
+
+    pub fn as_lock(&self) -> HassResult<LockState> {
+        self.typed("lock")
+    }
+
+    pub fn as_fan(&self) -> HassResult<FanState> {
+        self.typed("fan")
+    }
+
+    pub fn as_vacuum(&self) -> HassResult<VacuumState> {
+        self.typed("vacuum")
+    }
+
+    pub fn as_weather(&self) -> HassResult<WeatherState> {
+        self.typed("weather")
+    }
+}
+
+/// The state of lights, binary sensors, fans and other switch-like entities
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OnOff {
+    On,
+    Off,
+}
+
+/// This is the state of a climate entity
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HvacMode {
+    Off,
+    Heat,
+    Cool,
+    HeatCool,
+    Auto,
+    Dry,
+    FanOnly,
+}
+
+/// This is part of WeatherAttributes, some integrations send the bearing as a compass point
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum WindBearing {
+    /// degrees, clockwise from the north
+    Degrees(f64),
+    /// e.g. `NW`
+    Compass(String),
+}
+
+/// This is the state of a cover entity
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverStatus {
+    Open,
+    Closed,
+    Opening,
+    Closing,
+}
+
+/// This is the state of a media player entity
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaPlayerStatus {
+    Off,
+    On,
+    Idle,
+    Playing,
+    Paused,
+    Standby,
+    Buffering,
+}
+
+/// This is the state of a lock entity
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum LockStatus {
+    Locked,
+    Unlocked,
+    Locking,
+    Unlocking,
+    Jammed,
+    Open,
+    Opening,
+}
+
+/// This is the state of a vacuum entity
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum VacuumStatus {
+    Cleaning,
+    Docked,
+    Idle,
+    Paused,
+    Returning,
+    Error,
+}
+
+/// This is the state of a weather entity
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum WeatherCondition {
+    ClearNight,
+    Cloudy,
+    Exceptional,
+    Fog,
+    Hail,
+    Lightning,
+    LightningRainy,
+    Partlycloudy,
+    Pouring,
+    Rainy,
+    Snowy,
+    SnowyRainy,
+    Sunny,
+    Windy,
+    WindyVariant,
+}
+
+/// This is part of LightState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LightAttributes {
+    pub friendly_name: Option<String>,
+    pub brightness: Option<u8>,
+    pub color_mode: Option<String>,
+    pub supported_color_modes: Option<Vec<String>>,
+    pub color_temp_kelvin: Option<u32>,
+    pub min_color_temp_kelvin: Option<u32>,
+    pub max_color_temp_kelvin: Option<u32>,
+    pub hs_color: Option<(f64, f64)>,
+    pub rgb_color: Option<(u8, u8, u8)>,
+    pub xy_color: Option<(f64, f64)>,
+    pub effect: Option<String>,
+    pub effect_list: Option<Vec<String>>,
+    pub supported_features: Option<u32>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of ClimateState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ClimateAttributes {
+    pub friendly_name: Option<String>,
+    pub hvac_modes: Option<Vec<HvacMode>>,
+    pub hvac_action: Option<String>,
+    pub current_temperature: Option<f64>,
+    pub temperature: Option<f64>,
+    pub target_temp_high: Option<f64>,
+    pub target_temp_low: Option<f64>,
+    pub target_temp_step: Option<f64>,
+    pub min_temp: Option<f64>,
+    pub max_temp: Option<f64>,
+    pub current_humidity: Option<f64>,
+    pub humidity: Option<f64>,
+    pub fan_mode: Option<String>,
+    pub fan_modes: Option<Vec<String>>,
+    pub preset_mode: Option<String>,
+    pub preset_modes: Option<Vec<String>>,
+    pub swing_mode: Option<String>,
+    pub swing_modes: Option<Vec<String>>,
+    pub supported_features: Option<u32>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of CoverState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CoverAttributes {
+    pub friendly_name: Option<String>,
+    pub device_class: Option<String>,
+    pub current_position: Option<u8>,
+    pub current_tilt_position: Option<u8>,
+    pub supported_features: Option<u32>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of SensorState
+///
+/// Sensors with a non numeric state, e.g. enum or timestamp sensors, have the state kept as
+/// `StateValue::Other`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SensorAttributes {
+    pub friendly_name: Option<String>,
+    pub unit_of_measurement: Option<String>,
+    pub device_class: Option<String>,
+    pub state_class: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of BinarySensorState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct BinarySensorAttributes {
+    pub friendly_name: Option<String>,
+    pub device_class: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of MediaPlayerState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct MediaPlayerAttributes {
+    pub friendly_name: Option<String>,
+    pub device_class: Option<String>,
+    pub volume_level: Option<f64>,
+    pub is_volume_muted: Option<bool>,
+    pub media_content_id: Option<String>,
+    pub media_content_type: Option<String>,
+    pub media_title: Option<String>,
+    pub media_artist: Option<String>,
+    pub media_album_name: Option<String>,
+    pub media_duration: Option<f64>,
+    pub media_position: Option<f64>,
+    pub app_name: Option<String>,
+    pub source: Option<String>,
+    pub source_list: Option<Vec<String>>,
+    pub supported_features: Option<u32>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of LockState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LockAttributes {
+    pub friendly_name: Option<String>,
+    pub changed_by: Option<String>,
+    pub code_format: Option<String>,
+    pub supported_features: Option<u32>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of FanState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FanAttributes {
+    pub friendly_name: Option<String>,
+    pub percentage: Option<u8>,
+    pub percentage_step: Option<f64>,
+    pub preset_mode: Option<String>,
+    pub preset_modes: Option<Vec<String>>,
+    pub oscillating: Option<bool>,
+    pub direction: Option<String>,
+    pub supported_features: Option<u32>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of VacuumState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct VacuumAttributes {
+    pub friendly_name: Option<String>,
+    pub battery_level: Option<u8>,
+    pub fan_speed: Option<String>,
+    pub fan_speed_list: Option<Vec<String>>,
+    pub status: Option<String>,
+    pub supported_features: Option<u32>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// This is part of WeatherState
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct WeatherAttributes {
+    pub friendly_name: Option<String>,
+    pub temperature: Option<f64>,
+    pub temperature_unit: Option<String>,
+    pub apparent_temperature: Option<f64>,
+    pub dew_point: Option<f64>,
+    pub humidity: Option<f64>,
+    pub cloud_coverage: Option<f64>,
+    pub uv_index: Option<f64>,
+    pub pressure: Option<f64>,
+    pub pressure_unit: Option<String>,
+    pub wind_bearing: Option<WindBearing>,
+    pub wind_speed: Option<f64>,
+    pub wind_speed_unit: Option<String>,
+    pub visibility: Option<f64>,
+    pub visibility_unit: Option<String>,
+    pub precipitation_unit: Option<String>,
+    pub attribution: Option<String>,
+    pub supported_features: Option<u32>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The statistics grouped by statistic id, as returned by `recorder/statistics_during_period`
+///
+/// [Long-term statistics](https://developers.home-assistant.io/docs/core/entity/sensor/#long-term-statistics)
+pub type HassStatistics = HashMap<String, Vec<StatisticValue>>;
+
+/// The aggregation period used by `recorder/statistics_during_period`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub enum StatisticsPeriod {
+    #[serde(rename = "5minute")]
+    FiveMinute,
+    #[serde(rename = "hour")]
+    Hour,
+    #[serde(rename = "day")]
+    Day,
+    #[serde(rename = "week")]
+    Week,
+    #[serde(rename = "month")]
+    Month,
+}
+
+/// The kind of statistic to be returned for each period
+///
+/// Only `Mean` and `Sum` are accepted as filter by `recorder/list_statistic_ids`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum StatisticType {
+    Change,
+    LastReset,
+    Max,
+    Mean,
+    Min,
+    State,
+    Sum,
+}
+
+/// This object represents the metadata of a long-term statistic
+///
+/// returned by `recorder/list_statistic_ids` and `recorder/get_statistics_metadata`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct HassStatisticMetadata {
+    pub statistic_id: String,
+    pub display_unit_of_measurement: Option<String>,
+    #[serde(default)]
+    pub has_mean: bool,
+    #[serde(default)]
+    pub has_sum: bool,
+    pub mean_type: Option<u8>,
+    pub name: Option<String>,
+    pub source: String,
+    pub statistics_unit_of_measurement: Option<String>,
+    pub unit_class: Option<String>,
+}
+
+/// One entry of a statistic series
+///
+/// `start`, `end` and `last_reset` are milliseconds since the UNIX epoch
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct StatisticValue {
+    pub start: f64,
+    pub end: f64,
+    pub change: Option<f64>,
+    pub last_reset: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub min: Option<f64>,
+    pub state: Option<f64>,
+    pub sum: Option<f64>,
+}
+
+#[cfg(feature = "chrono")]
+impl StatisticValue {
+    /// The beginning of the period
+    pub fn start_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.start / 1000.0)
+    }
+
+    /// The end of the period
+    pub fn end_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.end / 1000.0)
+    }
+
+    /// The last time the meter was reset, if the statistic has a sum
+    pub fn last_reset_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        super::timestamp::from_epoch(self.last_reset? / 1000.0)
+    }
+}
+
+/// The aggregated result of `recorder/statistic_during_period`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct HassStatisticDuringPeriod {
+    pub change: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub min: Option<f64>,
+}
+
+/// The time window used by `recorder/statistic_during_period`
+///
+/// Timestamps are ISO 8601 strings, e.g. `2024-02-15T11:00:00+00:00`
+#[derive(Debug, Serialize, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum StatisticWindow {
+    /// A calendar period, e.g. the current day, optionally shifted by `offset` periods
+    Calendar {
+        period: CalendarPeriod,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        offset: Option<i32>,
+    },
+    /// An explicit period, open ended when the boundaries are not set
+    FixedPeriod {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        start_time: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        end_time: Option<String>,
+    },
+    /// A window of `duration` ending `offset` before now
+    RollingWindow {
+        duration: TimePeriod,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        offset: Option<TimePeriod>,
+    },
+}
+
+/// This is part of StatisticWindow
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarPeriod {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// This is part of StatisticWindow, a duration expressed the way Home Assistant expects it
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct TimePeriod {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hours: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seconds: Option<u32>,
+}
+
+/// The metadata of a statistic imported with `recorder/import_statistics`
+///
+/// External statistics use a `source` other than `recorder` and a `statistic_id` like `source:name`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct StatisticImportMetadata {
+    pub has_mean: bool,
+    pub has_sum: bool,
+    pub name: Option<String>,
+    pub source: String,
+    pub statistic_id: String,
+    pub unit_of_measurement: Option<String>,
+}
+
+/// One entry of a statistic series imported with `recorder/import_statistics`
+///
+/// `start` is an ISO 8601 timestamp that has to be aligned to a full hour
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct StatisticImportValue {
+    pub start: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_reset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sum: Option<f64>,
+}
@@ -0,0 +1,20 @@
+//! Conversions of the Home Assistant timestamps, enabled by the `chrono` feature
+//!
+//! Home Assistant serializes the state and event times as ISO 8601 strings, e.g.
+//! `2024-03-01T10:20:30.123456+00:00`, and the registry times as epoch seconds.
+
+use chrono::{DateTime, Utc};
+
+/// Parses an ISO 8601 timestamp into UTC, `None` if it is malformed
+pub(crate) fn parse_iso(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Converts epoch seconds with a fractional part into UTC
+pub(crate) fn from_epoch(seconds: f64) -> Option<DateTime<Utc>> {
+    let secs = seconds.floor();
+    let nanos = ((seconds - secs) * 1e9).round().min(999_999_999.0) as u32;
+    DateTime::from_timestamp(secs as i64, nanos)
+}
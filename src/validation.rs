@@ -0,0 +1,403 @@
+//! Client-side validation of service calls
+//!
+//! The service data and target are checked against the schema returned by `get_services`,
+//! so a wrong call fails with the offending fields instead of an opaque `ResponseError`.
+//! The services without any described field are not checked for unknown fields, as Home
+//! Assistant does not expose their schema.
+
+use crate::services::ServiceCall;
+use crate::types::{EntityFilter, Field, HassService, HassServices, Selector, Target};
+use crate::{HassError, HassResult};
+
+use serde_json::Value;
+use std::fmt;
+
+/// the target keys also accepted in the service data
+const TARGET_KEYS: &[&str] = &["entity_id", "device_id", "area_id", "floor_id", "label_id"];
+
+/// ServiceValidator checks service calls against a cached `HassServices`
+///
+/// ```no_run
+/// # async fn run(client: &mut hass_rs::HassClient) -> hass_rs::HassResult<()> {
+/// use hass_rs::validation::ServiceValidator;
+/// use serde_json::json;
+///
+/// let validator = ServiceValidator::new(client.get_services().await?);
+/// let data = json!({"entity_id": "light.kitchen", "brightness_pct": 40});
+/// validator.validate("light", "turn_on", Some(&data), None)?;
+/// client
+///     .call_service("light".to_owned(), "turn_on".to_owned(), Some(data))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServiceValidator {
+    services: HassServices,
+}
+
+impl ServiceValidator {
+    pub fn new(services: HassServices) -> Self {
+        Self { services }
+    }
+
+    pub fn services(&self) -> &HassServices {
+        &self.services
+    }
+
+    /// Checks the service data and target of a call, returns `HassError::InvalidServiceCall`
+    /// listing every offending field
+    #[allow(clippy::result_large_err)]
+    pub fn validate(
+        &self,
+        domain: &str,
+        service: &str,
+        service_data: Option<&Value>,
+        target: Option<&Target>,
+    ) -> HassResult<()> {
+        let errors = match self.services.0.get(domain).and_then(|s| s.get(service)) {
+            Some(hass_service) => check_call(hass_service, service_data, target),
+            None => vec![ValidationError::new(
+                "",
+                ValidationErrorKind::UnknownService,
+            )],
+        };
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(HassError::InvalidServiceCall {
+                domain: domain.to_owned(),
+                service: service.to_owned(),
+                errors,
+            })
+        }
+    }
+
+    /// Checks a call built with one of the typed requests of the `services` module
+    #[allow(clippy::result_large_err)]
+    pub fn validate_call<S: ServiceCall>(&self, call: &S) -> HassResult<()> {
+        let service_data = call.service_data()?;
+        self.validate(
+            call.domain(),
+            call.service(),
+            service_data.as_ref(),
+            call.target(),
+        )
+    }
+}
+
+/// An offending field of a service call
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// the path of the value, e.g. `brightness`, `entity_id[1]` or `target.entity_id[0]`
+    pub path: String,
+    pub kind: ValidationErrorKind,
+}
+
+impl ValidationError {
+    fn new(path: &str, kind: ValidationErrorKind) -> Self {
+        Self {
+            path: path.to_owned(),
+            kind,
+        }
+    }
+}
+
+/// This is part of ValidationError
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationErrorKind {
+    /// The domain or the service does not exist
+    UnknownService,
+    /// The field is not part of the service schema
+    UnknownField,
+    /// The field is required but missing
+    MissingField,
+    /// The value has not the type expected by the selector, e.g. a string for a number
+    InvalidType { expected: String },
+    /// The number is outside of the selector range
+    OutOfRange {
+        value: f64,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    /// The value is not one of the select options
+    InvalidOption { value: String, options: Vec<String> },
+    /// The entity does not belong to one of the accepted domains
+    UnexpectedDomain {
+        entity_id: String,
+        domains: Vec<String>,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ValidationErrorKind::UnknownService => write!(f, "unknown service"),
+            ValidationErrorKind::UnknownField => write!(f, "{}: unknown field", self.path),
+            ValidationErrorKind::MissingField => {
+                write!(f, "{}: required field is missing", self.path)
+            }
+            ValidationErrorKind::InvalidType { expected } => {
+                write!(f, "{}: expected {}", self.path, expected)
+            }
+            ValidationErrorKind::OutOfRange { value, min, max } => write!(
+                f,
+                "{}: {} is outside of [{}, {}]",
+                self.path,
+                value,
+                min.map_or("-inf".to_owned(), |min| min.to_string()),
+                max.map_or("inf".to_owned(), |max| max.to_string())
+            ),
+            ValidationErrorKind::InvalidOption { value, options } => {
+                write!(f, "{}: {:?} is not one of {:?}", self.path, value, options)
+            }
+            ValidationErrorKind::UnexpectedDomain { entity_id, domains } => write!(
+                f,
+                "{}: {} is not one of the domains {:?}",
+                self.path, entity_id, domains
+            ),
+        }
+    }
+}
+
+fn check_call(
+    hass_service: &HassService,
+    service_data: Option<&Value>,
+    target: Option<&Target>,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let empty = serde_json::Map::new();
+    let data = match service_data {
+        None | Some(Value::Null) => &empty,
+        Some(Value::Object(data)) => data,
+        Some(_) => {
+            let expected = "an object".to_owned();
+            errors.push(ValidationError::new(
+                "service_data",
+                ValidationErrorKind::InvalidType { expected },
+            ));
+            return errors;
+        }
+    };
+
+    for (name, field) in hass_service.all_fields() {
+        match data.get(name) {
+            Some(value) => check_field(&mut errors, name, field, value),
+            None if field.required => errors.push(ValidationError::new(
+                name,
+                ValidationErrorKind::MissingField,
+            )),
+            None => {}
+        }
+    }
+
+    let has_schema = !hass_service.fields.is_empty() || !hass_service.sections.is_empty();
+    for (name, value) in data {
+        if hass_service
+            .all_fields()
+            .any(|(field_name, _)| field_name == name)
+        {
+            continue;
+        }
+        match &hass_service.target {
+            // the target can also be set in the service data
+            Some(target) if name == "entity_id" => {
+                check_entities(&mut errors, name, value, true, &target.entity, &[])
+            }
+            Some(_) if TARGET_KEYS.contains(&name.as_str()) => {}
+            _ if has_schema => errors.push(ValidationError::new(
+                name,
+                ValidationErrorKind::UnknownField,
+            )),
+            _ => {}
+        }
+    }
+
+    if let Some(target) = target {
+        match &hass_service.target {
+            Some(target_selector) => {
+                for (i, entity_id) in target.entity_id.iter().enumerate() {
+                    let path = format!("target.entity_id[{i}]");
+                    check_entity(&mut errors, &path, entity_id, &target_selector.entity, &[]);
+                }
+            }
+            None if !target.is_empty() => {
+                errors.push(ValidationError::new(
+                    "target",
+                    ValidationErrorKind::UnknownField,
+                ));
+            }
+            None => {}
+        }
+    }
+
+    errors
+}
+
+fn check_field(errors: &mut Vec<ValidationError>, path: &str, field: &Field, value: &Value) {
+    let Some(selector) = &field.selector else {
+        return;
+    };
+
+    match selector {
+        Selector::Number(number) => {
+            let Some(value) = as_number(value) else {
+                let expected = "a number".to_owned();
+                errors.push(ValidationError::new(
+                    path,
+                    ValidationErrorKind::InvalidType { expected },
+                ));
+                return;
+            };
+            let (min, max) = (number.min, number.max);
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                errors.push(ValidationError::new(
+                    path,
+                    ValidationErrorKind::OutOfRange { value, min, max },
+                ));
+            }
+        }
+        Selector::Select(select) => {
+            for_each_value(
+                errors,
+                path,
+                value,
+                select.multiple,
+                |errors, path, value| {
+                    let Some(option) = value.as_str() else {
+                        let expected = "a string".to_owned();
+                        errors.push(ValidationError::new(
+                            path,
+                            ValidationErrorKind::InvalidType { expected },
+                        ));
+                        return;
+                    };
+                    if !select.accepts(option) {
+                        errors.push(ValidationError::new(
+                            path,
+                            ValidationErrorKind::InvalidOption {
+                                value: option.to_owned(),
+                                options: select
+                                    .options
+                                    .iter()
+                                    .map(|option| option.value().to_owned())
+                                    .collect(),
+                            },
+                        ));
+                    }
+                },
+            );
+        }
+        Selector::Entity(entity) => {
+            let filters: Vec<EntityFilter> = entity.filters().cloned().collect();
+            check_entities(
+                errors,
+                path,
+                value,
+                entity.multiple,
+                &filters,
+                &entity.include_entities,
+            );
+        }
+        Selector::Boolean if !value.is_boolean() => {
+            let expected = "a boolean".to_owned();
+            errors.push(ValidationError::new(
+                path,
+                ValidationErrorKind::InvalidType { expected },
+            ));
+        }
+        _ => {}
+    }
+}
+
+/// Checks an entity id, or a list of entity ids if `multiple`, against the domain filters, the
+/// entities of `include` being accepted whatever the filters
+fn check_entities(
+    errors: &mut Vec<ValidationError>,
+    path: &str,
+    value: &Value,
+    multiple: bool,
+    filters: &[EntityFilter],
+    include: &[String],
+) {
+    for_each_value(
+        errors,
+        path,
+        value,
+        multiple,
+        |errors, path, value| match value.as_str() {
+            Some(entity_id) => check_entity(errors, path, entity_id, filters, include),
+            None => {
+                let expected = "an entity id".to_owned();
+                errors.push(ValidationError::new(
+                    path,
+                    ValidationErrorKind::InvalidType { expected },
+                ));
+            }
+        },
+    );
+}
+
+/// The entity has to match any of the filters, only their domains are checked: a filter
+/// without domain, e.g. on the integration only, accepts any entity
+fn check_entity(
+    errors: &mut Vec<ValidationError>,
+    path: &str,
+    entity_id: &str,
+    filters: &[EntityFilter],
+    include: &[String],
+) {
+    // `all` targets every entity of the service domain
+    if entity_id == "all" || include.iter().any(|included| included == entity_id) {
+        return;
+    }
+    let filters: Vec<&EntityFilter> = filters.iter().filter(|f| !f.is_empty()).collect();
+    if filters.is_empty() || filters.iter().any(|filter| filter.domain.is_empty()) {
+        return;
+    }
+
+    let domain = entity_id.split_once('.').map(|(domain, _)| domain);
+    let matches =
+        |filter: &&EntityFilter| domain.is_some_and(|d| filter.domain.iter().any(|f| f == d));
+    if !filters.iter().any(matches) {
+        let domains = filters
+            .iter()
+            .flat_map(|filter| filter.domain.iter().cloned())
+            .collect();
+        errors.push(ValidationError::new(
+            path,
+            ValidationErrorKind::UnexpectedDomain {
+                entity_id: entity_id.to_owned(),
+                domains,
+            },
+        ));
+    }
+}
+
+/// Calls `check` on the value, or on each item with its index in the path for a list
+fn for_each_value(
+    errors: &mut Vec<ValidationError>,
+    path: &str,
+    value: &Value,
+    multiple: bool,
+    mut check: impl FnMut(&mut Vec<ValidationError>, &str, &Value),
+) {
+    match value {
+        Value::Array(values) if multiple => {
+            for (i, value) in values.iter().enumerate() {
+                check(errors, &format!("{path}[{i}]"), value);
+            }
+        }
+        value => check(errors, path, value),
+    }
+}
+
+/// Home Assistant coerces the numeric strings
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(number) => number.trim().parse().ok(),
+        _ => None,
+    }
+}
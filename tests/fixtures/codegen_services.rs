@@ -0,0 +1,160 @@
+// Generated by hass-rs from the Home Assistant services, do not edit.
+
+pub mod my_custom {
+    #![allow(clippy::all)]
+
+    #[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+    pub struct Ping {
+    }
+
+    impl hass_rs::services::ServiceCall for Ping {
+        fn domain(&self) -> &str {
+            "my-custom"
+        }
+
+        fn service(&self) -> &str {
+            "ping"
+        }
+
+        fn target(&self) -> Option<&hass_rs::Target> {
+            None
+        }
+
+        fn service_data(&self) -> hass_rs::HassResult<Option<serde_json::Value>> {
+            match serde_json::to_value(self)? {
+                serde_json::Value::Object(map) if map.is_empty() => Ok(None),
+                value => Ok(Some(value)),
+            }
+        }
+    }
+}
+
+pub mod my_custom_ {
+    #![allow(clippy::all)]
+
+    #[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+    pub struct Svc2 {
+    }
+
+    impl hass_rs::services::ServiceCall for Svc2 {
+        fn domain(&self) -> &str {
+            "my_custom"
+        }
+
+        fn service(&self) -> &str {
+            "Svc2"
+        }
+
+        fn target(&self) -> Option<&hass_rs::Target> {
+            None
+        }
+
+        fn service_data(&self) -> hass_rs::HassResult<Option<serde_json::Value>> {
+            match serde_json::to_value(self)? {
+                serde_json::Value::Object(map) if map.is_empty() => Ok(None),
+                value => Ok(Some(value)),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+    pub struct Aim {
+        #[serde(skip)]
+        pub target: hass_rs::Target,
+
+        #[serde(rename = "a-b")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub a_b: Option<bool>,
+
+        #[serde(rename = "a_b")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub a_b_: Option<bool>,
+
+        #[serde(rename = "target")]
+        pub target_: String,
+    }
+
+    impl hass_rs::services::ServiceCall for Aim {
+        fn domain(&self) -> &str {
+            "my_custom"
+        }
+
+        fn service(&self) -> &str {
+            "aim"
+        }
+
+        fn target(&self) -> Option<&hass_rs::Target> {
+            Some(&self.target)
+        }
+
+        fn service_data(&self) -> hass_rs::HassResult<Option<serde_json::Value>> {
+            match serde_json::to_value(self)? {
+                serde_json::Value::Object(map) if map.is_empty() => Ok(None),
+                value => Ok(Some(value)),
+            }
+        }
+    }
+
+    /// Does the thing.
+    #[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+    pub struct DoThing {
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub entities: Option<Vec<String>>,
+
+        /// The level
+        pub level: f64,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub payload: Option<serde_json::Value>,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub r#type: Option<String>,
+    }
+
+    impl hass_rs::services::ServiceCall for DoThing {
+        fn domain(&self) -> &str {
+            "my_custom"
+        }
+
+        fn service(&self) -> &str {
+            "do-thing"
+        }
+
+        fn target(&self) -> Option<&hass_rs::Target> {
+            None
+        }
+
+        fn service_data(&self) -> hass_rs::HassResult<Option<serde_json::Value>> {
+            match serde_json::to_value(self)? {
+                serde_json::Value::Object(map) if map.is_empty() => Ok(None),
+                value => Ok(Some(value)),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+    pub struct Svc22 {
+    }
+
+    impl hass_rs::services::ServiceCall for Svc22 {
+        fn domain(&self) -> &str {
+            "my_custom"
+        }
+
+        fn service(&self) -> &str {
+            "svc2"
+        }
+
+        fn target(&self) -> Option<&hass_rs::Target> {
+            None
+        }
+
+        fn service_data(&self) -> hass_rs::HassResult<Option<serde_json::Value>> {
+            match serde_json::to_value(self)? {
+                serde_json::Value::Object(map) if map.is_empty() => Ok(None),
+                value => Ok(Some(value)),
+            }
+        }
+    }
+}
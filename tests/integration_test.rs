@@ -1,6 +1,13 @@
 use futures_util::{SinkExt, StreamExt};
 use hass_rs::client::HassClient;
 use hass_rs::errors::HassError;
+use hass_rs::services::light;
+use hass_rs::types::{
+    ConfigEntryChangeType, ConfigEntryState, HassEntity, HassIssueAction, HassServices,
+    IntegrationType, OnOff, Selector, StateValue, StatisticType, StatisticsPeriod, Target,
+};
+use std::collections::HashMap;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio_tungstenite::accept_async;
 use tokio_tungstenite::tungstenite::Message;
@@ -130,6 +137,60 @@ async fn test_ping_pong() {
     server_task.await.unwrap();
 }
 
+#[cfg(all(feature = "smol-runtime", not(feature = "tokio-runtime")))]
+#[tokio::test]
+async fn test_smol_runtime() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // the first ping is answered, the second one times out
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"ping""#));
+        ws.send(Message::Text(r#"{"id":1,"type":"pong"}"#.into()))
+            .await
+            .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""id":2"#));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+    });
+
+    // the client runs on a thread without any tokio runtime
+    let client_thread = std::thread::spawn(move || {
+        smol::block_on(async {
+            let mut client = HassClient::new(&url).await.unwrap();
+            client.auth_with_longlivedtoken("token").await.unwrap();
+            client.ping().await.unwrap();
+
+            client.set_request_timeout(Some(Duration::from_millis(100)));
+            let timed_out = client.ping().await;
+            assert!(matches!(timed_out, Err(HassError::Timeout(_))));
+        })
+    });
+
+    server_task.await.unwrap();
+    tokio::task::spawn_blocking(move || client_thread.join().unwrap())
+        .await
+        .unwrap();
+}
+
 #[tokio::test]
 async fn test_get_config() {
     let (listener, url) = setup_mock_server().await;
@@ -199,7 +260,9 @@ async fn test_get_config() {
 }
 
 #[tokio::test]
-async fn test_subscribe_events() {
+async fn test_get_and_update_core_config() {
+    use hass_rs::types::{CoreConfigUpdate, CoreState, UnitSystemName};
+
     let (listener, url) = setup_mock_server().await;
 
     let server_task = tokio::spawn(async move {
@@ -207,82 +270,100 @@ async fn test_subscribe_events() {
         let mut ws = accept_async(stream).await.unwrap();
 
         ws.send(Message::Text(
-            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+            r#"{"type":"auth_required","ha_version":"2025.1.0"}"#.into(),
         ))
         .await
         .unwrap();
-
-        let msg = ws.next().await.unwrap().unwrap();
-        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        let _auth = ws.next().await.unwrap().unwrap();
         ws.send(Message::Text(
-            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+            r#"{"type":"auth_ok","ha_version":"2025.1.0"}"#.into(),
         ))
         .await
         .unwrap();
 
-        // Expect subscribe_events command
-        let msg = ws.next().await.unwrap().unwrap();
-        let text = msg.to_text().unwrap();
-        assert!(text.contains(r#""type":"subscribe_events""#));
-        assert!(text.contains(r#""id":1"#));
+        let _get_config = ws.next().await.unwrap().unwrap();
+        let response_json = r#"{
+            "id": 1,
+            "type": "result",
+            "success": true,
+            "result": {
+                "latitude": 31.5,
+                "longitude": 35.5,
+                "elevation": -430,
+                "radius": 100,
+                "unit_system": {
+                    "length": "km",
+                    "accumulated_precipitation": "mm",
+                    "area": "m²",
+                    "mass": "g",
+                    "pressure": "Pa",
+                    "temperature": "°C",
+                    "volume": "L",
+                    "wind_speed": "m/s"
+                },
+                "location_name": "Dead Sea",
+                "time_zone": "Asia/Jerusalem",
+                "components": ["frontend"],
+                "config_dir": "/config",
+                "allowlist_external_dirs": ["/media"],
+                "allowlist_external_urls": [],
+                "version": "2025.1.0",
+                "config_source": "storage",
+                "recovery_mode": false,
+                "state": "RUNNING",
+                "external_url": null,
+                "internal_url": null,
+                "currency": "ILS",
+                "country": "IL",
+                "language": "he",
+                "safe_mode": false,
+                "debug": false,
+                "new_setting": 42
+            }
+        }"#;
+        ws.send(Message::Text(response_json.into())).await.unwrap();
 
-        // Respond with success
+        let msg = ws.next().await.unwrap().unwrap();
+        let request: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({
+                "id": 2,
+                "type": "config/core/update",
+                "unit_system": "us_customary",
+                "currency": "USD"
+            })
+        );
         ws.send(Message::Text(
-            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+            r#"{"id":2,"type":"result","success":true,"result":null}"#.into(),
         ))
         .await
         .unwrap();
-
-        // Send an event after a brief sleep to ensure client has registered the subscription channel
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-
-        let event_json = r#"{
-            "id": 1,
-            "type": "event",
-            "event": {
-                "event_type": "state_changed",
-                "data": {
-                    "entity_id": "light.kitchen",
-                    "new_state": {
-                        "entity_id": "light.kitchen",
-                        "state": "on",
-                        "attributes": {},
-                        "last_changed": "2024-02-15T11:13:02.291378+00:00",
-                        "last_updated": "2024-02-15T11:13:02.291378+00:00",
-                        "context": {
-                            "id": "01HPRMZAWNXKVVPSP11QFJ53HB",
-                            "parent_id": null,
-                            "user_id": null
-                        }
-                    },
-                    "old_state": null
-                },
-                "origin": "LOCAL",
-                "time_fired": "2024-02-15T11:13:02.291378+00:00",
-                "context": {
-                    "id": "01HPRMZAWNXKVVPSP11QFJ53HB",
-                    "parent_id": null,
-                    "user_id": null
-                }
-            }
-        }"#;
-        ws.send(Message::Text(event_json.into())).await.unwrap();
     });
 
     let mut client = HassClient::new(&url).await.unwrap();
     client.auth_with_longlivedtoken("token").await.unwrap();
 
-    let mut rx = client.subscribe_event("state_changed").await.unwrap();
-    let event = rx.recv().await.unwrap();
-    assert_eq!(event.event.event_type, "state_changed");
-    assert_eq!(event.event.data.entity_id.unwrap(), "light.kitchen");
-    assert_eq!(event.event.data.new_state.unwrap().state, "on");
+    let config = client.get_config().await.unwrap();
+    assert_eq!(config.elevation, -430);
+    assert_eq!(config.state, Some(CoreState::Running));
+    assert_eq!(config.currency.as_deref(), Some("ILS"));
+    assert_eq!(config.unit_system.wind_speed.as_deref(), Some("m/s"));
+    assert!(config.whitelist_external_dirs.is_empty());
+    assert_eq!(config.extra["new_setting"], 42);
+
+    let update = CoreConfigUpdate {
+        unit_system: Some(UnitSystemName::UsCustomary),
+        currency: Some("USD".to_owned()),
+        ..Default::default()
+    };
+    client.update_core_config(update).await.unwrap();
 
     server_task.await.unwrap();
 }
 
 #[tokio::test]
-async fn test_close_connection() {
+async fn test_raw_responses() {
     let (listener, url) = setup_mock_server().await;
 
     let server_task = tokio::spawn(async move {
@@ -290,46 +371,120 @@ async fn test_close_connection() {
         let mut ws = accept_async(stream).await.unwrap();
 
         ws.send(Message::Text(
-            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+            r#"{"type":"auth_required","ha_version":"2025.1.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+        let _auth = ws.next().await.unwrap().unwrap();
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2025.1.0"}"#.into(),
         ))
         .await
         .unwrap();
 
-        let msg = ws.next().await.unwrap().unwrap();
-        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        // require_admin was dropped by this imaginary server version
+        let _get_panels = ws.next().await.unwrap().unwrap();
         ws.send(Message::Text(
-            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+            r#"{"id":1,"type":"result","success":true,"result":{
+                "lovelace":{"component_name":"lovelace","config":null,"icon":null,
+                    "title":null,"url_path":"lovelace","show_in_sidebar":true}
+            }}"#
+            .into(),
         ))
         .await
         .unwrap();
 
-        // Expect get_config command
         let msg = ws.next().await.unwrap().unwrap();
-        assert!(msg.to_text().unwrap().contains(r#""type":"get_config""#));
+        let request: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({"id": 2, "type": "config/floor_registry/create", "name": "Attic"})
+        );
+        ws.send(Message::Text(
+            r#"{"id":2,"type":"result","success":true,"result":{"floor_id":"attic","name":"Attic"}}"#
+                .into(),
+        ))
+        .await
+        .unwrap();
 
-        // Send Close frame
-        ws.send(Message::Close(None)).await.unwrap();
+        let _delete = ws.next().await.unwrap().unwrap();
+        ws.send(Message::Text(
+            r#"{"id":3,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
     });
 
     let mut client = HassClient::new(&url).await.unwrap();
     client.auth_with_longlivedtoken("token").await.unwrap();
 
-    let res = client.get_config().await;
-    assert!(res.is_err());
-    if let Err(HassError::UnknownPayloadReceived(hass_rs::types::Response::Close(reason))) = res {
-        assert_eq!(reason, "");
-    } else {
-        panic!(
-            "Expected UnknownPayloadReceived(Response::Close) error, got {:?}",
-            res
-        );
-    }
+    let panels = client.get_panels_raw().await.unwrap();
+    assert!(panels.typed.is_err());
+    assert_eq!(panels.raw["lovelace"]["show_in_sidebar"], true);
+    assert!(matches!(
+        panels.into_typed(),
+        Err(HassError::UnableToDeserialize(_))
+    ));
+
+    let floor = client
+        .send_raw(
+            "config/floor_registry/create",
+            serde_json::json!({"name": "Attic"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(floor["floor_id"], "attic");
+
+    let deleted = client
+        .send_raw(
+            "config/floor_registry/delete",
+            serde_json::json!({"floor_id": "attic"}),
+        )
+        .await
+        .unwrap();
+    assert!(deleted.is_null());
+
+    assert!(client
+        .send_raw("config/floor_registry/list", serde_json::json!([1]))
+        .await
+        .is_err());
 
     server_task.await.unwrap();
 }
 
 #[tokio::test]
-async fn test_unsubscribe_event_manual() {
+async fn test_custom_commands() {
+    use hass_rs::command::{HassCommand, OneShot, Subscription};
+
+    #[derive(serde::Serialize)]
+    struct NodeRequest {
+        device_id: String,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct NodeStatus {
+        node_id: u32,
+        ready: bool,
+    }
+
+    struct GetNodeStatus;
+
+    impl HassCommand for GetNodeStatus {
+        const TYPE: &'static str = "zwave_js/node_status";
+        type Request = NodeRequest;
+        type Response = NodeStatus;
+        type Kind = OneShot;
+    }
+
+    struct SubscribeNodeStatus;
+
+    impl HassCommand for SubscribeNodeStatus {
+        const TYPE: &'static str = "zwave_js/subscribe_node_status";
+        type Request = NodeRequest;
+        type Response = NodeStatus;
+        type Kind = Subscription;
+    }
+
     let (listener, url) = setup_mock_server().await;
 
     let server_task = tokio::spawn(async move {
@@ -337,56 +492,123 @@ async fn test_unsubscribe_event_manual() {
         let mut ws = accept_async(stream).await.unwrap();
 
         ws.send(Message::Text(
-            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+            r#"{"type":"auth_required","ha_version":"2025.1.0"}"#.into(),
         ))
         .await
         .unwrap();
-
-        let msg = ws.next().await.unwrap().unwrap();
-        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        let _auth = ws.next().await.unwrap().unwrap();
         ws.send(Message::Text(
-            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+            r#"{"type":"auth_ok","ha_version":"2025.1.0"}"#.into(),
         ))
         .await
         .unwrap();
 
-        // 1. Expect subscribe_events command (ID = 1)
         let msg = ws.next().await.unwrap().unwrap();
-        assert!(msg.to_text().unwrap().contains(r#""type":"subscribe_events""#));
+        let request: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({"id": 1, "type": "zwave_js/node_status", "device_id": "abc"})
+        );
         ws.send(Message::Text(
-            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+            r#"{"id":1,"type":"result","success":true,"result":{"node_id":4,"ready":true}}"#.into(),
         ))
         .await
         .unwrap();
 
-        // 2. Expect unsubscribe_events command (ID = 2) for subscription 1
         let msg = ws.next().await.unwrap().unwrap();
-        let text = msg.to_text().unwrap();
-        assert!(text.contains(r#""type":"unsubscribe_events""#));
-        assert!(text.contains(r#""subscription":1"#));
-        assert!(text.contains(r#""id":2"#));
-
-        // Respond with success to unsubscribe command
+        assert!(msg.to_text().unwrap().contains(r#""id":2"#));
         ws.send(Message::Text(
             r#"{"id":2,"type":"result","success":true,"result":null}"#.into(),
         ))
         .await
         .unwrap();
+        ws.send(Message::Text(
+            r#"{"id":2,"type":"event","event":{"node_id":4,"ready":false}}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // never answered, the client times out before the connection is closed
+        let _ignored = ws.next().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
     });
 
     let mut client = HassClient::new(&url).await.unwrap();
     client.auth_with_longlivedtoken("token").await.unwrap();
 
-    let _rx = client.subscribe_event("state_changed").await.unwrap();
-
-    let unsub_res = client.unsubscribe_event(1).await;
-    assert!(unsub_res.is_ok());
+    let request = NodeRequest {
+        device_id: "abc".to_owned(),
+    };
+    let status = client.send::<GetNodeStatus>(request).await.unwrap();
+    assert_eq!(
+        status,
+        NodeStatus {
+            node_id: 4,
+            ready: true
+        }
+    );
+
+    let request = NodeRequest {
+        device_id: "abc".to_owned(),
+    };
+    let mut subscription = client.send::<SubscribeNodeStatus>(request).await.unwrap();
+    let event = subscription.recv().await.unwrap().unwrap();
+    assert!(!event.ready);
+
+    client.set_request_timeout(Some(Duration::from_millis(50)));
+    let request = NodeRequest {
+        device_id: "abc".to_owned(),
+    };
+    assert!(matches!(
+        client.send::<GetNodeStatus>(request).await,
+        Err(HassError::Timeout(_))
+    ));
 
     server_task.await.unwrap();
 }
 
 #[tokio::test]
-async fn test_unsubscribe_event_auto() {
+async fn test_client_metrics() {
+    use hass_rs::metrics::{HassMetrics, RequestOutcome};
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    // the metrics, and the requests in flight
+    #[derive(Default)]
+    struct Recorded(Mutex<Vec<String>>, AtomicI64);
+
+    impl HassMetrics for Recorded {
+        fn connection_opened(&self) {
+            self.0.lock().unwrap().push("connection".to_owned());
+        }
+
+        fn request_started(&self, _msg_type: &str) {
+            self.1.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn request_finished(&self, msg_type: &str, _latency: Duration, outcome: RequestOutcome) {
+            self.1.fetch_sub(1, Ordering::SeqCst);
+            let request = format!("{msg_type}:{}", outcome.as_str());
+            self.0.lock().unwrap().push(request);
+        }
+
+        fn event_received(&self, msg_type: &str) {
+            self.0.lock().unwrap().push(format!("event:{msg_type}"));
+        }
+
+        fn event_dropped(&self, msg_type: &str) {
+            self.0.lock().unwrap().push(format!("dropped:{msg_type}"));
+        }
+
+        fn deserialization_failed(&self) {
+            self.0.lock().unwrap().push("malformed".to_owned());
+        }
+
+        fn ping_rtt(&self, _rtt: Duration) {
+            self.0.lock().unwrap().push("rtt".to_owned());
+        }
+    }
+
     let (listener, url) = setup_mock_server().await;
 
     let server_task = tokio::spawn(async move {
@@ -398,66 +620,1735 @@ async fn test_unsubscribe_event_auto() {
         ))
         .await
         .unwrap();
-
-        let msg = ws.next().await.unwrap().unwrap();
-        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        let _auth = ws.next().await.unwrap().unwrap();
         ws.send(Message::Text(
             r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
         ))
         .await
         .unwrap();
 
-        // 1. Expect subscribe_events command (ID = 1)
-        let msg = ws.next().await.unwrap().unwrap();
-        assert!(msg.to_text().unwrap().contains(r#""type":"subscribe_events""#));
+        let _ping = ws.next().await.unwrap().unwrap();
+        ws.send(Message::Text("{not json".into())).await.unwrap();
         ws.send(Message::Text(
-            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+            r#"{"id":42,"type":"event","event":{"node_id":4}}"#.into(),
         ))
         .await
         .unwrap();
+        ws.send(Message::Text(r#"{"id":1,"type":"pong"}"#.into()))
+            .await
+            .unwrap();
 
-        // 2. Wait a bit for subscription setup on client side, then send an event
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-
-        let event_json = r#"{
-            "id": 1,
-            "type": "event",
-            "event": {
-                "event_type": "state_changed",
-                "data": {
-                    "entity_id": "light.kitchen",
-                    "new_state": null,
-                    "old_state": null
-                },
-                "origin": "LOCAL",
-                "time_fired": "2024-02-15T11:13:02.291378+00:00",
-                "context": {
-                    "id": "01HPRMZAWNXKVVPSP11QFJ53HB",
-                    "parent_id": null,
-                    "user_id": null
-                }
-            }
-        }"#;
-        ws.send(Message::Text(event_json.into())).await.unwrap();
+        let _subscribe = ws.next().await.unwrap().unwrap();
+        ws.send(Message::Text(
+            r#"{"id":2,"type":"result","success":false,"error":{"code":"unknown_command","message":"Unknown command."}}"#.into(),
+        ))
+        .await
+        .unwrap();
 
-        // 3. Expect auto unsubscribe command from client background loop
         let msg = ws.next().await.unwrap().unwrap();
-        let text = msg.to_text().unwrap();
-        assert!(text.contains(r#""type":"unsubscribe_events""#));
-        assert!(text.contains(r#""subscription":1"#));
+        assert!(msg
+            .to_text()
+            .unwrap()
+            .contains(r#""type":"logbook/event_stream""#));
+        ws.send(Message::Text(
+            r#"{"id":3,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+        ws.send(Message::Text(
+            r#"{"id":3,"type":"event","event":{"events":[]}}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // never answered, the client times out before the connection is closed
+        let _ignored = ws.next().await.unwrap().unwrap();
+        // never answered, the caller drops the request
+        let _cancelled = ws.next().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
     });
 
-    let mut client = HassClient::new(&url).await.unwrap();
+    let recorded = Arc::new(Recorded::default());
+    let mut client = HassClient::with_metrics(&url, recorded.clone())
+        .await
+        .unwrap();
     client.auth_with_longlivedtoken("token").await.unwrap();
+    client.ping().await.unwrap();
+    assert!(client
+        .subscribe_event("zwave_js_value_updated")
+        .await
+        .is_err());
+
+    let mut logbook = client
+        .subscribe_logbook("2024-02-15T00:00:00Z", None, None, None)
+        .await
+        .unwrap();
+    logbook.recv().await.unwrap().unwrap();
+
+    client.set_request_timeout(Some(Duration::from_millis(50)));
+    assert!(client.get_states().await.is_err());
+
+    client.set_request_timeout(None);
+    let config = tokio::time::timeout(Duration::from_millis(50), client.get_config());
+    assert!(config.await.is_err());
+    assert_eq!(recorded.1.load(Ordering::SeqCst), 0);
+
+    let recorded = recorded.0.lock().unwrap().clone();
+    assert_eq!(
+        recorded,
+        [
+            "connection",
+            "auth:success",
+            "malformed",
+            "dropped:unknown",
+            "ping:success",
+            "rtt",
+            "subscribe_events:error",
+            "logbook/event_stream:success",
+            "event:logbook/event_stream",
+            "get_states:timeout",
+            "get_config:cancelled",
+        ]
+    );
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_subscribe_events() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // Expect subscribe_events command
+        let msg = ws.next().await.unwrap().unwrap();
+        let text = msg.to_text().unwrap();
+        assert!(text.contains(r#""type":"subscribe_events""#));
+        assert!(text.contains(r#""id":1"#));
+
+        // Respond with success
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // Send an event after a brief sleep to ensure client has registered the subscription channel
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+
+        let event_json = r#"{
+            "id": 1,
+            "type": "event",
+            "event": {
+                "event_type": "state_changed",
+                "data": {
+                    "entity_id": "light.kitchen",
+                    "new_state": {
+                        "entity_id": "light.kitchen",
+                        "state": "on",
+                        "attributes": {},
+                        "last_changed": "2024-02-15T11:13:02.291378+00:00",
+                        "last_updated": "2024-02-15T11:13:02.291378+00:00",
+                        "context": {
+                            "id": "01HPRMZAWNXKVVPSP11QFJ53HB",
+                            "parent_id": null,
+                            "user_id": null
+                        }
+                    },
+                    "old_state": null
+                },
+                "origin": "LOCAL",
+                "time_fired": "2024-02-15T11:13:02.291378+00:00",
+                "context": {
+                    "id": "01HPRMZAWNXKVVPSP11QFJ53HB",
+                    "parent_id": null,
+                    "user_id": null
+                }
+            }
+        }"#;
+        ws.send(Message::Text(event_json.into())).await.unwrap();
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let mut rx = client.subscribe_event("state_changed").await.unwrap();
+    let event = rx.recv().await.unwrap();
+    assert_eq!(event.event.event_type, "state_changed");
+    assert_eq!(event.event.data.entity_id.unwrap(), "light.kitchen");
+    assert_eq!(event.event.data.new_state.unwrap().state, "on");
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_close() {
+    let (listener, url) = setup_mock_server().await;
+    let (stalled_tx, stalled_rx) = tokio::sync::oneshot::channel();
+
+    let server_task = tokio::spawn(async move {
+        for subscribed in [true, false] {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+
+            ws.send(Message::Text(
+                r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+            ))
+            .await
+            .unwrap();
+            let _auth = ws.next().await.unwrap().unwrap();
+            ws.send(Message::Text(
+                r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+            ))
+            .await
+            .unwrap();
+
+            if subscribed {
+                let _subscribe = ws.next().await.unwrap().unwrap();
+                ws.send(Message::Text(
+                    r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+                ))
+                .await
+                .unwrap();
+
+                let msg = ws.next().await.unwrap().unwrap();
+                let msg = msg.to_text().unwrap();
+                assert!(msg.contains(r#""type":"unsubscribe_events""#));
+                assert!(msg.contains(r#""subscription":1"#));
+            }
+
+            // closed by `close` on the first connection, by the drop on the second one
+            let msg = ws.next().await.unwrap().unwrap();
+            assert!(msg.is_close());
+            // answers the close frame
+            assert!(ws.next().await.is_none());
+        }
+
+        // the third connection stops reading, the writer of the client gets stuck
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+        let _auth = ws.next().await.unwrap().unwrap();
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+        stalled_rx.await.unwrap();
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+    let mut events = client.subscribe_event("state_changed").await.unwrap();
+
+    client.close().await;
+    assert!(events.recv().await.is_none());
+    assert!(matches!(client.ping().await, Err(HassError::ClientClosed)));
+    // closing twice does nothing
+    client.close().await;
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+    drop(client);
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+    let payload = serde_json::json!({"data": "x".repeat(32 << 20)});
+    let pending = tokio::time::timeout(
+        Duration::from_millis(200),
+        client.send_raw("my/command", payload),
+    );
+    assert!(pending.await.is_err());
+    // bounded even though the close frame can't be written
+    tokio::time::timeout(Duration::from_secs(5), client.close())
+        .await
+        .unwrap();
+    stalled_tx.send(()).unwrap();
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_server_version_gating() {
+    use hass_rs::command::{HassCommand, Subscription};
+    use hass_rs::HaVersion;
+
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2024.3.3"}"#.into(),
+        ))
+        .await
+        .unwrap();
+        let _auth = ws.next().await.unwrap().unwrap();
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2024.3.3"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // the floor and label registries are never requested
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"ping""#));
+        ws.send(Message::Text(r#"{"id":1,"type":"pong"}"#.into()))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let info = client.server_info().unwrap();
+    assert_eq!(info.ha_version, HaVersion::new(2024, 3, 3));
+    assert!(info.supports(&HaVersion::new(2023, 12, 0)));
+
+    match client.get_floor_registry_list().await {
+        Err(HassError::UnsupportedByServer { required, actual }) => {
+            assert_eq!(required.to_string(), "2024.4.0");
+            assert_eq!(actual.to_string(), "2024.3.3");
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    assert!(matches!(
+        client.get_label_registry_list().await,
+        Err(HassError::UnsupportedByServer { .. })
+    ));
+
+    // a custom command can declare the release it needs
+    struct SubscribeEntities;
+    impl HassCommand for SubscribeEntities {
+        const TYPE: &'static str = "subscribe_entities";
+        type Request = ();
+        type Response = serde_json::Value;
+        type Kind = Subscription;
+        const MIN_VERSION: Option<HaVersion> = Some(HaVersion::new(2024, 4, 0));
+    }
+    assert!(matches!(
+        client.send::<SubscribeEntities>(()).await,
+        Err(HassError::UnsupportedByServer { .. })
+    ));
+    client.ping().await.unwrap();
+
+    let beta: HaVersion = "2024.6.0b1".parse().unwrap();
+    assert_eq!(beta.pre.as_deref(), Some("b1"));
+    assert!(beta < HaVersion::new(2024, 6, 0));
+    assert!(beta > HaVersion::new(2024, 5, 4));
+    let beta10: HaVersion = "2024.6.0b10".parse().unwrap();
+    assert!(beta10 > "2024.6.0b9".parse().unwrap());
+    assert!(beta10 < "2024.6.0rc1".parse().unwrap());
+    assert!("2024.6.0.dev20240501".parse::<HaVersion>().unwrap() < beta);
+    // the betas of a release already have its features
+    let info = hass_rs::ServerInfo {
+        ha_version: "2024.4.0b3".parse().unwrap(),
+    };
+    assert!(info.supports(&HaVersion::new(2024, 4, 0)));
+    assert!(!info.supports(&HaVersion::new(2024, 4, 1)));
+    assert!("0.118.5".parse::<HaVersion>().unwrap() < HaVersion::new(2021, 1, 0));
+    assert_eq!(
+        "2024.6".parse::<HaVersion>().unwrap(),
+        HaVersion::new(2024, 6, 0)
+    );
+    assert!(matches!(
+        "dev".parse::<HaVersion>(),
+        Err(HassError::InvalidVersion(_))
+    ));
+
+    server_task.await.unwrap();
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_client() {
+    // the mock server runs on its own runtime, the client on the internal one
+    let server = tokio::runtime::Runtime::new().unwrap();
+    let (listener, url) = server.block_on(setup_mock_server());
+
+    let server_task = server.spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"get_states""#));
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":[{
+                "entity_id": "light.kitchen",
+                "state": "on",
+                "attributes": {},
+                "last_changed": "2024-02-15T11:13:02.291378+00:00",
+                "last_updated": "2024-02-15T11:13:02.291378+00:00",
+                "context": {"id": "01HPRMZAWNXKVVPSP11QFJ53HB", "parent_id": null, "user_id": null}
+            }]}"#
+                .into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"subscribe_events""#));
+        ws.send(Message::Text(
+            r#"{"id":2,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // the events are forwarded while the client thread is not in a call
+        for state in ["off", "on"] {
+            let event = format!(
+                r#"{{"id":2,"type":"event","event":{{"event_type":"state_changed",
+                "data":{{"entity_id":"light.kitchen","new_state":{{"entity_id":"light.kitchen",
+                "state":"{state}","attributes":{{}},
+                "last_changed":"2024-02-15T11:13:02.291378+00:00",
+                "last_updated":"2024-02-15T11:13:02.291378+00:00",
+                "context":{{"id":"01HPRMZAWNXKVVPSP11QFJ53HB","parent_id":null,"user_id":null}}}},
+                "old_state":null}},"origin":"LOCAL","time_fired":"2024-02-15T11:13:02.291378+00:00",
+                "context":{{"id":"01HPRMZAWNXKVVPSP11QFJ53HB","parent_id":null,"user_id":null}}}}}}"#
+            );
+            ws.send(Message::Text(event.into())).await.unwrap();
+        }
+    });
+
+    let mut client = hass_rs::blocking::HassClient::new(&url).unwrap();
+    client.auth_with_longlivedtoken("token").unwrap();
+
+    let states = client.get_states().unwrap();
+    assert_eq!(states[0].entity_id, "light.kitchen");
+
+    let events = client.subscribe_event("state_changed").unwrap();
+    let states: Vec<String> = events
+        .iter()
+        .take(2)
+        .map(|event| event.event.data.new_state.unwrap().state)
+        .collect();
+    assert_eq!(states, ["off", "on"]);
+
+    server.block_on(server_task).unwrap();
+}
+
+#[tokio::test]
+async fn test_close_connection() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // Expect get_config command
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"get_config""#));
+
+        // Send Close frame
+        ws.send(Message::Close(None)).await.unwrap();
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let res = client.get_config().await;
+    assert!(res.is_err());
+    if let Err(HassError::UnknownPayloadReceived(hass_rs::types::Response::Close(reason))) = res {
+        assert_eq!(reason, "");
+    } else {
+        panic!(
+            "Expected UnknownPayloadReceived(Response::Close) error, got {:?}",
+            res
+        );
+    }
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unsubscribe_event_manual() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // 1. Expect subscribe_events command (ID = 1)
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg
+            .to_text()
+            .unwrap()
+            .contains(r#""type":"subscribe_events""#));
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // 2. Expect unsubscribe_events command (ID = 2) for subscription 1
+        let msg = ws.next().await.unwrap().unwrap();
+        let text = msg.to_text().unwrap();
+        assert!(text.contains(r#""type":"unsubscribe_events""#));
+        assert!(text.contains(r#""subscription":1"#));
+        assert!(text.contains(r#""id":2"#));
+
+        // Respond with success to unsubscribe command
+        ws.send(Message::Text(
+            r#"{"id":2,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let _rx = client.subscribe_event("state_changed").await.unwrap();
+
+    let unsub_res = client.unsubscribe_event(1).await;
+    assert!(unsub_res.is_ok());
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unsubscribe_event_auto() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2021.3.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // 1. Expect subscribe_events command (ID = 1)
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg
+            .to_text()
+            .unwrap()
+            .contains(r#""type":"subscribe_events""#));
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // 2. Wait a bit for subscription setup on client side, then send an event
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+
+        let event_json = r#"{
+            "id": 1,
+            "type": "event",
+            "event": {
+                "event_type": "state_changed",
+                "data": {
+                    "entity_id": "light.kitchen",
+                    "new_state": null,
+                    "old_state": null
+                },
+                "origin": "LOCAL",
+                "time_fired": "2024-02-15T11:13:02.291378+00:00",
+                "context": {
+                    "id": "01HPRMZAWNXKVVPSP11QFJ53HB",
+                    "parent_id": null,
+                    "user_id": null
+                }
+            }
+        }"#;
+        ws.send(Message::Text(event_json.into())).await.unwrap();
+
+        // 3. Expect auto unsubscribe command from client background loop
+        let msg = ws.next().await.unwrap().unwrap();
+        let text = msg.to_text().unwrap();
+        assert!(text.contains(r#""type":"unsubscribe_events""#));
+        assert!(text.contains(r#""subscription":1"#));
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let rx = client.subscribe_event("state_changed").await.unwrap();
+
+    // Explicitly drop rx to trigger auto-unsubscribe on next received event
+    drop(rx);
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_statistics_during_period() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        let text = msg.to_text().unwrap();
+        assert!(text.contains(r#""type":"recorder/statistics_during_period""#));
+        assert!(text.contains(r#""period":"5minute""#));
+        assert!(text.contains(r#""types":["sum","last_reset"]"#));
+        assert!(text.contains(r#""units":{"energy":"kWh"}"#));
+        assert!(!text.contains("end_time"));
+
+        let response_json = r#"{
+            "id": 1,
+            "type": "result",
+            "success": true,
+            "result": {
+                "sensor.energy_meter": [
+                    {"start": 1707994800000.0, "end": 1707995100000.0, "sum": 12.5, "last_reset": null}
+                ]
+            }
+        }"#;
+        ws.send(Message::Text(response_json.into())).await.unwrap();
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let units = HashMap::from([("energy".to_owned(), "kWh".to_owned())]);
+    let statistics = client
+        .statistics_during_period(
+            "2024-02-15T11:00:00+00:00",
+            None,
+            vec!["sensor.energy_meter".to_owned()],
+            StatisticsPeriod::FiveMinute,
+            vec![StatisticType::Sum, StatisticType::LastReset],
+            Some(units),
+        )
+        .await
+        .unwrap();
+
+    let series = &statistics["sensor.energy_meter"];
+    assert_eq!(series.len(), 1);
+    assert_eq!(series[0].sum, Some(12.5));
+    assert_eq!(series[0].mean, None);
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_subscribe_logbook() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        let text = msg.to_text().unwrap();
+        assert!(text.contains(r#""type":"logbook/event_stream""#));
+        assert!(text.contains(r#""entity_ids":["light.kitchen"]"#));
+
+        // The historical entries are sent right after the result, without waiting
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let event_json = r#"{
+            "id": 1,
+            "type": "event",
+            "event": {
+                "events": [
+                    {
+                        "when": 1707995582.291378,
+                        "name": "Kitchen",
+                        "state": "on",
+                        "entity_id": "light.kitchen",
+                        "context_user_id": "f069978dd7964042824cb09287fe7c73",
+                        "context_event_type": "call_service",
+                        "context_domain": "light",
+                        "context_service": "turn_on"
+                    }
+                ],
+                "start_time": 1707994800.0,
+                "end_time": 1707995600.0,
+                "partial": false
+            }
+        }"#;
+        ws.send(Message::Text(event_json.into())).await.unwrap();
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let mut logbook = client
+        .subscribe_logbook(
+            "2024-02-15T11:00:00+00:00",
+            None,
+            Some(vec!["light.kitchen".to_owned()]),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(logbook.id(), 1);
+
+    let message = logbook.recv().await.unwrap().unwrap();
+    assert_eq!(message.partial, Some(false));
+    let entry = &message.events[0];
+    assert_eq!(entry.entity_id.as_deref(), Some("light.kitchen"));
+    assert_eq!(entry.context_service.as_deref(), Some("turn_on"));
+    assert_eq!(
+        entry.context_user_id.as_deref(),
+        Some("f069978dd7964042824cb09287fe7c73")
+    );
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_subscribe_config_entries() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        let text = msg.to_text().unwrap();
+        assert!(text.contains(r#""type":"config_entries/subscribe""#));
+        assert!(text.contains(r#""type_filter":["device","hub"]"#));
+
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let event_json = r#"{
+            "id": 1,
+            "type": "event",
+            "event": [
+                {
+                    "type": "updated",
+                    "entry": {
+                        "entry_id": "01HPRMZAWNXKVVPSP11QFJ53HB",
+                        "domain": "hue",
+                        "title": "Hue Bridge",
+                        "source": "zeroconf",
+                        "state": "setup_retry",
+                        "supports_options": true,
+                        "supports_remove_device": true,
+                        "supports_unload": true,
+                        "pref_disable_new_entities": false,
+                        "pref_disable_polling": false,
+                        "disabled_by": null,
+                        "reason": "Connection refused",
+                        "error_reason_translation_key": null,
+                        "error_reason_translation_placeholders": null
+                    }
+                }
+            ]
+        }"#;
+        ws.send(Message::Text(event_json.into())).await.unwrap();
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let mut changes = client
+        .subscribe_config_entries(Some(vec![IntegrationType::Device, IntegrationType::Hub]))
+        .await
+        .unwrap();
+
+    let change = changes.recv().await.unwrap().unwrap().remove(0);
+    assert_eq!(change.change_type, Some(ConfigEntryChangeType::Updated));
+    assert_eq!(change.entry.state, ConfigEntryState::SetupRetry);
+    assert!(change.entry.is_failed());
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_repairs_ignore_and_subscribe() {
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        let text = msg.to_text().unwrap();
+        assert!(text.contains(r#""type":"subscribe_events""#));
+        assert!(text.contains(r#""event_type":"repairs_issue_registry_updated""#));
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        let text = msg.to_text().unwrap();
+        assert!(text.contains(r#""type":"repairs/ignore_issue""#));
+        assert!(text.contains(r#""issue_id":"deprecated_yaml""#));
+        assert!(text.contains(r#""ignore":true"#));
+        ws.send(Message::Text(
+            r#"{"id":2,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let event_json = r#"{
+            "id": 1,
+            "type": "event",
+            "event": {
+                "event_type": "repairs_issue_registry_updated",
+                "data": {"action": "update", "domain": "mqtt", "issue_id": "deprecated_yaml"},
+                "origin": "LOCAL",
+                "time_fired": "2024-02-15T11:13:02.291378+00:00",
+                "context": {"id": "01HPRMZAWNXKVVPSP11QFJ53HB", "parent_id": null, "user_id": null}
+            }
+        }"#;
+        ws.send(Message::Text(event_json.into())).await.unwrap();
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let mut issues = client.subscribe_issues().await.unwrap();
+    client
+        .ignore_issue("mqtt", "deprecated_yaml", true)
+        .await
+        .unwrap();
+
+    let event = issues.recv().await.unwrap().unwrap();
+    assert_eq!(event.data.action, HassIssueAction::Update);
+    assert_eq!(event.data.issue_id, "deprecated_yaml");
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_call_service_typed() {
+    use hass_rs::services::{climate, media_player, notify, ServiceCall};
+    use hass_rs::types::HvacMode;
+
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"auth""#));
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2024.2.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        let command: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+        assert_eq!(
+            command,
+            serde_json::json!({
+                "id": 1,
+                "type": "call_service",
+                "domain": "light",
+                "service": "turn_on",
+                "service_data": {"brightness_pct": 40, "rgb_color": [255, 120, 0]},
+                "target": {"entity_id": ["light.kitchen"]}
+            })
+        );
+
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":{"context":{"id":"01HPRMZAWNXKVVPSP11QFJ53HB","parent_id":null,"user_id":null}}}"#.into(),
+        ))
+        .await
+        .unwrap();
+    });
 
-    let rx = client.subscribe_event("state_changed").await.unwrap();
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
 
-    // Explicitly drop rx to trigger auto-unsubscribe on next received event
-    drop(rx);
+    let turn_on = light::TurnOn {
+        brightness_pct: Some(40),
+        rgb_color: Some((255, 120, 0)),
+        ..light::TurnOn::new(Target::entity("light.kitchen"))
+    };
+    client.call_service_typed(&turn_on).await.unwrap();
+
+    // the required fields of a service are arguments of `new`
+    let hvac_mode = climate::SetHvacMode::new(Target::entity("climate.hall"), HvacMode::Heat);
+    assert_eq!(
+        hvac_mode.service_data().unwrap(),
+        Some(serde_json::json!({"hvac_mode": "heat"}))
+    );
+    let message = notify::SendMessage::new(Target::entity("notify.phone"), "Door open");
+    assert_eq!(
+        message.service_data().unwrap(),
+        Some(serde_json::json!({"message": "Door open"}))
+    );
+    let play = media_player::PlayMedia::new(Target::entity("media_player.tv"), "42", "music");
+    assert_eq!(
+        play.service_data().unwrap(),
+        Some(serde_json::json!({"media_content_id": "42", "media_content_type": "music"}))
+    );
+
+    server_task.await.unwrap();
+}
+
+/// Answers one HTTP request with the provided JSON body, returning the request line and body
+#[cfg(feature = "rest")]
+async fn serve_http_once(listener: &TcpListener, response_body: &str) -> (String, String) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    let (head, body) = loop {
+        let n = stream.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            if body.len() >= length {
+                break (head.to_owned(), body.to_owned());
+            }
+        }
+    };
+    assert!(head.contains("authorization: Bearer token"));
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        response_body.len(),
+        response_body
+    );
+    stream.write_all(response.as_bytes()).await.unwrap();
+    (head.lines().next().unwrap().to_owned(), body)
+}
+
+#[cfg(feature = "rest")]
+#[tokio::test]
+async fn test_config_flow_driver() {
+    use hass_rs::flow::{FlowDriver, FlowKind};
+    use hass_rs::types::FlowStep;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let server_task = tokio::spawn(async move {
+        let (request_line, body) = serve_http_once(
+            &listener,
+            r#"{"type":"form","flow_id":"abc","handler":"met","step_id":"user","data_schema":[{"name":"name","required":true,"type":"string"}],"errors":{},"description_placeholders":null,"last_step":null,"preview":null}"#,
+        )
+        .await;
+        assert_eq!(
+            request_line,
+            "POST /api/config/config_entries/flow HTTP/1.1"
+        );
+        assert!(body.contains(r#""handler":"met""#));
+
+        let (request_line, body) = serve_http_once(
+            &listener,
+            r#"{"type":"create_entry","flow_id":"abc","handler":"met","title":"Home","description":null,"description_placeholders":null,"result":{"entry_id":"01HQ"}}"#,
+        )
+        .await;
+        assert_eq!(
+            request_line,
+            "POST /api/config/config_entries/flow/abc HTTP/1.1"
+        );
+        assert_eq!(body, r#"{"name":"Home"}"#);
+    });
+
+    let driver = FlowDriver::new(&url, "token", FlowKind::Config);
+
+    let step = driver.start("met").await.unwrap();
+    let FlowStep::Form(form) = &step else {
+        panic!("Expected a form step, got {:?}", step);
+    };
+    assert_eq!(form.data_schema[0].name, "name");
+    assert!(form.data_schema[0].required);
+
+    let step = driver
+        .submit(step.flow_id(), serde_json::json!({"name": "Home"}))
+        .await
+        .unwrap();
+    assert!(step.is_finished());
+    assert!(matches!(step, FlowStep::CreateEntry(entry) if entry.title.as_deref() == Some("Home")));
+
+    // a repair flow needs the issue, it is not started from a handler only
+    let repair = FlowDriver::new(&url, "token", FlowKind::Repair);
+    assert!(matches!(
+        repair.start("hue").await,
+        Err(HassError::Generic(_))
+    ));
+
+    server_task.await.unwrap();
+}
+
+#[cfg(feature = "rest")]
+#[tokio::test]
+async fn test_config_flow_progress() {
+    use hass_rs::flow::{FlowDriver, FlowKind};
+    use hass_rs::types::FlowStep;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let server_task = tokio::spawn(async move {
+        serve_http_once(
+            &listener,
+            r#"{"type":"progress","flow_id":"abc","handler":"hue","step_id":"link","progress_action":"wait_for_button","description_placeholders":null}"#,
+        )
+        .await;
+
+        let (request_line, _) = serve_http_once(
+            &listener,
+            r#"{"type":"progress_done","flow_id":"abc","handler":"hue","step_id":"finish"}"#,
+        )
+        .await;
+        assert_eq!(
+            request_line,
+            "GET /api/config/config_entries/flow/abc HTTP/1.1"
+        );
+
+        serve_http_once(
+            &listener,
+            r#"{"type":"create_entry","flow_id":"abc","handler":"hue","title":"Hue Bridge","description":null,"description_placeholders":null,"result":{"entry_id":"01HR"}}"#,
+        )
+        .await;
+    });
+
+    let driver = FlowDriver::new(&url, "token", FlowKind::Config);
+
+    let step = driver.start("hue").await.unwrap();
+    assert!(
+        matches!(&step, FlowStep::Progress(progress) if progress.progress_action == "wait_for_button")
+    );
+
+    let step = driver.progress(step.flow_id()).await.unwrap();
+    let FlowStep::ProgressDone(done) = &step else {
+        panic!("Expected a progress done step, got {:?}", step);
+    };
+    assert_eq!(done.step_id.as_deref(), Some("finish"));
+    assert_eq!(
+        serde_json::to_value(&step).unwrap()["type"],
+        "progress_done"
+    );
+
+    let step = driver
+        .submit(step.flow_id(), serde_json::json!({}))
+        .await
+        .unwrap();
+    assert!(matches!(step, FlowStep::CreateEntry(_)));
+
+    server_task.await.unwrap();
+}
+
+#[cfg(feature = "rest")]
+#[tokio::test]
+async fn test_rest_client() {
+    use hass_rs::rest::HassRestClient;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let server_task = tokio::spawn(async move {
+        let (request_line, body) = serve_http_once(
+            &listener,
+            r#"{"entity_id":"sensor.backup","state":"done","attributes":{"friendly_name":"Backup"},
+            "last_changed":"2024-02-15T11:13:02.291378+00:00","last_updated":"2024-02-15T11:13:02.291378+00:00",
+            "context":{"id":"01HPRMZAWNXKVVPSP11QFJ53HB","parent_id":null,"user_id":null}}"#,
+        )
+        .await;
+        assert_eq!(request_line, "POST /api/states/sensor.backup HTTP/1.1");
+        assert_eq!(
+            body,
+            r#"{"attributes":{"friendly_name":"Backup"},"state":"done"}"#
+        );
+
+        let (request_line, body) = serve_http_once(&listener, "Hello above_horizon").await;
+        assert_eq!(request_line, "POST /api/template HTTP/1.1");
+        assert_eq!(body, r#"{"template":"Hello {{ states('sun.sun') }}"}"#);
+
+        let (request_line, _) = serve_http_once(
+            &listener,
+            r#"[{"domain":"homeassistant","services":{"restart":{"name":"Restart","description":"Restarts Home Assistant.","fields":{}}}}]"#,
+        )
+        .await;
+        assert_eq!(request_line, "GET /api/services HTTP/1.1");
+
+        let (request_line, _) = serve_http_once(&listener, "[[]]").await;
+        assert_eq!(
+            request_line,
+            "GET /api/history/period/2024-02-15T00:00:00Z?filter_entity_id=light.kitchen%2Clight.hall&end_time=2024-02-16T00%3A00%3A00Z HTTP/1.1"
+        );
+
+        let (request_line, _) = serve_http_once(
+            &listener,
+            r#"[{"summary":"Holiday","start":{"date":"2024-06-01"},"end":{"date":"2024-06-02"},
+            "description":null,"location":null,"uid":"abc","recurrence_id":null,"rrule":"FREQ=YEARLY"}]"#,
+        )
+        .await;
+        assert!(request_line.starts_with("GET /api/calendars/calendar.home?start="));
+
+        let (request_line, _) = serve_http_once(
+            &listener,
+            r#"{"result":"invalid","errors":"Integration error: foo","warnings":null}"#,
+        )
+        .await;
+        assert_eq!(request_line, "POST /api/config/core/check_config HTTP/1.1");
+
+        let (request_line, _) = serve_http_once(&listener, r#"{"require_restart":true}"#).await;
+        assert_eq!(
+            request_line,
+            "DELETE /api/config/config_entries/entry/01HQ HTTP/1.1"
+        );
+    });
+
+    let client = HassRestClient::new(&url, "token");
+
+    let entity = client
+        .set_state(
+            "sensor.backup",
+            "done",
+            serde_json::json!({"friendly_name": "Backup"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(entity.state, "done");
+
+    let greeting = client
+        .render_template("Hello {{ states('sun.sun') }}")
+        .await
+        .unwrap();
+    assert_eq!(greeting, "Hello above_horizon");
+
+    let services = client.get_services().await.unwrap();
+    assert_eq!(
+        services.0["homeassistant"]["restart"].name.as_deref(),
+        Some("Restart")
+    );
+
+    let history = client
+        .get_history(
+            "2024-02-15T00:00:00Z",
+            Some("2024-02-16T00:00:00Z"),
+            &["light.kitchen", "light.hall"],
+        )
+        .await
+        .unwrap();
+    assert_eq!(history, vec![vec![]]);
+    assert!(matches!(
+        client.get_history("2024-02-15T00:00:00Z", None, &[]).await,
+        Err(HassError::Generic(_))
+    ));
+
+    let events = client
+        .get_calendar_events(
+            "calendar.home",
+            "2024-06-01T00:00:00Z",
+            "2024-07-01T00:00:00Z",
+        )
+        .await
+        .unwrap();
+    assert!(events[0].is_all_day());
+    assert_eq!(events[0].rrule.as_deref(), Some("FREQ=YEARLY"));
+
+    let check = client.check_config().await.unwrap();
+    assert!(!check.is_valid());
+    assert_eq!(check.errors.as_deref(), Some("Integration error: foo"));
+
+    assert!(client.delete_config_entry("01HQ").await.unwrap());
+
+    server_task.await.unwrap();
+}
+
+#[cfg(feature = "rest")]
+#[tokio::test]
+async fn test_state_publisher() {
+    use hass_rs::publisher::{StatePublisher, VirtualEntity};
+    use hass_rs::rest::HassRestClient;
+    use hass_rs::EntityId;
+
+    const ENTITY: &str = r#"{"entity_id":"sensor.office_occupancy","state":"0.8","attributes":{},
+        "last_changed":"2024-02-15T11:13:02.291378+00:00","last_updated":"2024-02-15T11:13:02.291378+00:00",
+        "context":null}"#;
+
+    let http = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let http_url = format!("http://{}", http.local_addr().unwrap());
+    let (listener, url) = setup_mock_server().await;
+    let (restarted_tx, restarted_rx) = tokio::sync::oneshot::channel::<()>();
+    let (dropped_tx, dropped_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let ws_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2024.6.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+        let _auth = ws.next().await.unwrap().unwrap();
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2024.6.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg
+            .to_text()
+            .unwrap()
+            .contains(r#""event_type":"homeassistant_started""#));
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        restarted_rx.await.unwrap();
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"event","event":{"event_type":"homeassistant_started","data":{},
+            "origin":"LOCAL","time_fired":"2024-02-15T11:13:02.291378+00:00",
+            "context":{"id":"01HPRMZAWNXKVVPSP11QFJ53HB","parent_id":null,"user_id":null}}}"#
+                .into(),
+        ))
+        .await
+        .unwrap();
+
+        // the restart closes the connection
+        dropped_rx.await.unwrap();
+        drop(ws);
+    });
+
+    let http_task = tokio::spawn(async move {
+        // the first state right away, then the last one of the interval
+        for expected in [
+            r#"{"attributes":{"friendly_name":"Office occupancy","unit_of_measurement":"%"},"state":"0.8"}"#,
+            r#"{"attributes":{"friendly_name":"Office occupancy","unit_of_measurement":"%"},"state":"0.6"}"#,
+        ] {
+            let (request_line, body) = serve_http_once(&http, ENTITY).await;
+            assert_eq!(
+                request_line,
+                "POST /api/states/sensor.office_occupancy HTTP/1.1"
+            );
+            assert_eq!(body, expected);
+        }
+
+        // republished once Home Assistant started
+        restarted_tx.send(()).unwrap();
+        let (_, body) = serve_http_once(&http, ENTITY).await;
+        assert!(body.contains(r#""state":"0.6""#));
+
+        // republished once Home Assistant runs again after the connection was lost
+        dropped_tx.send(()).unwrap();
+        let (request_line, _) = serve_http_once(
+            &http,
+            r#"{"latitude":52.37,"longitude":4.89,"location_name":"Home",
+            "time_zone":"Europe/Amsterdam","version":"2024.6.0","state":"RUNNING"}"#,
+        )
+        .await;
+        assert_eq!(request_line, "GET /api/config HTTP/1.1");
+        let (request_line, body) = serve_http_once(&http, ENTITY).await;
+        assert_eq!(
+            request_line,
+            "POST /api/states/sensor.office_occupancy HTTP/1.1"
+        );
+        assert!(body.contains(r#""state":"0.6""#));
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let rest = HassRestClient::new(&http_url, "token");
+    let publisher = StatePublisher::new(rest, Duration::from_millis(100));
+    publisher.republish_on_restart(&mut client).await.unwrap();
+
+    let mut occupancy =
+        VirtualEntity::new(EntityId::new("sensor.office_occupancy").unwrap(), "0.8");
+    occupancy.unit_of_measurement = Some("%".to_owned());
+    occupancy.friendly_name = Some("Office occupancy".to_owned());
+    assert!(publisher.publish(&occupancy).await.unwrap());
+    // unchanged
+    assert!(!publisher.publish(&occupancy).await.unwrap());
+
+    // rate limited, only the last one is pushed
+    for state in ["0.7", "0.6"] {
+        occupancy.state = state.to_owned();
+        assert!(!publisher.publish(&occupancy).await.unwrap());
+    }
+
+    http_task.await.unwrap();
+    ws_task.await.unwrap();
+}
+
+#[cfg(feature = "automation")]
+#[tokio::test]
+async fn test_automation_engine() {
+    use chrono::{NaiveTime, TimeZone, Weekday};
+    use hass_rs::automation::{
+        Action, Automation, AutomationEngine, Condition, RunOutcome, Snapshot, StepResult, Trigger,
+    };
+    use hass_rs::services::light;
+    use hass_rs::{EntityId, HassEntity, Target};
+
+    fn entity(entity_id: &str, state: &str) -> String {
+        format!(
+            r#"{{"entity_id":"{entity_id}","state":"{state}","attributes":{{}},
+            "last_changed":"2024-02-15T11:13:02.291378+00:00",
+            "last_updated":"2024-02-15T11:13:02.291378+00:00","context":null}}"#
+        )
+    }
+
+    fn state_changed(old: &str, new: &str) -> String {
+        format!(
+            r#"{{"id":3,"type":"event","event":{{"event_type":"state_changed",
+            "data":{{"entity_id":"light.kitchen","old_state":{},"new_state":{}}},
+            "origin":"LOCAL","time_fired":"2024-02-15T11:13:02.291378+00:00",
+            "context":{{"id":"01HPRMZAWNXKVVPSP11QFJ53HB","parent_id":null,"user_id":null}}}}}}"#,
+            entity("light.kitchen", old),
+            entity("light.kitchen", new)
+        )
+    }
+
+    let (listener, url) = setup_mock_server().await;
+
+    let server_task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::Text(
+            r#"{"type":"auth_required","ha_version":"2024.6.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+        let _auth = ws.next().await.unwrap().unwrap();
+        ws.send(Message::Text(
+            r#"{"type":"auth_ok","ha_version":"2024.6.0"}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"get_config""#));
+        ws.send(Message::Text(
+            r#"{"id":1,"type":"result","success":true,"result":{"latitude":52.37,"longitude":4.89,
+            "location_name":"Home","time_zone":"Europe/Amsterdam","version":"2024.6.0"}}"#
+                .into(),
+        ))
+        .await
+        .unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg.to_text().unwrap().contains(r#""type":"get_states""#));
+        let states = format!(
+            r#"{{"id":2,"type":"result","success":true,"result":[{},{}]}}"#,
+            entity("light.kitchen", "off"),
+            entity("sensor.kitchen_lux", "20")
+        );
+        ws.send(Message::Text(states.into())).await.unwrap();
+
+        let msg = ws.next().await.unwrap().unwrap();
+        assert!(msg
+            .to_text()
+            .unwrap()
+            .contains(r#""event_type":"state_changed""#));
+        ws.send(Message::Text(
+            r#"{"id":3,"type":"result","success":true,"result":null}"#.into(),
+        ))
+        .await
+        .unwrap();
+
+        // an attribute change does not fire the trigger, the change to on does
+        ws.send(Message::Text(state_changed("off", "off").into()))
+            .await
+            .unwrap();
+        ws.send(Message::Text(state_changed("off", "on").into()))
+            .await
+            .unwrap();
 
+        let msg = ws.next().await.unwrap().unwrap();
+        let msg = msg.to_text().unwrap();
+        assert!(msg.contains(r#""type":"call_service""#));
+        assert!(msg.contains(r#""service":"turn_on""#));
+        assert!(msg.contains(r#""light.hall""#));
+        ws.send(Message::Text(
+            r#"{"id":4,"type":"result","success":true,"result":{"context":{"id":"01HQ","parent_id":null,"user_id":null}}}"#
+                .into(),
+        ))
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    });
+
+    let mut client = HassClient::new(&url).await.unwrap();
+    client.auth_with_longlivedtoken("token").await.unwrap();
+
+    let kitchen = EntityId::new("light.kitchen").unwrap();
+    let mut automation = Automation::new("hall follows kitchen");
+    automation.triggers.push(Trigger::State {
+        entity_id: kitchen.clone(),
+        from: None,
+        to: Some("on".to_owned()),
+        for_: None,
+    });
+    automation.conditions.push(Condition::NumericState {
+        entity_id: EntityId::new("sensor.kitchen_lux").unwrap(),
+        attribute: None,
+        above: None,
+        below: Some(50.0),
+    });
+    automation
+        .actions
+        .push(Action::service(&light::TurnOn::new(Target::entity("light.hall"))).unwrap());
+
+    let mut engine = AutomationEngine::new(client);
+    engine.add(automation);
+    let traces = engine.traces();
+
+    // runs until the server closes the connection
+    engine.run().await.unwrap();
     server_task.await.unwrap();
+
+    let traces = traces.get("hall follows kitchen");
+    assert_eq!(traces.len(), 1);
+    assert_eq!(traces[0].outcome, RunOutcome::Finished);
+    assert_eq!(traces[0].trigger.entity_id.as_ref(), Some(&kitchen));
+    let steps: Vec<_> = traces[0]
+        .steps
+        .iter()
+        .map(|step| (step.path.as_str(), step.result.clone()))
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            ("condition/0", StepResult::Passed),
+            ("action/0", StepResult::Done)
+        ]
+    );
+
+    // the conditions are checked without a server
+    let states: HashMap<EntityId, HassEntity> = HashMap::new();
+    let templates = HashMap::from([
+        (
+            "{{ is_state('sun.sun', 'below_horizon') }}".to_owned(),
+            "True".to_owned(),
+        ),
+        ("{{ 0 }}".to_owned(), "0".to_owned()),
+    ]);
+    let snapshot = Snapshot {
+        states: &states,
+        // a Saturday night
+        now: chrono_tz::Europe::Amsterdam
+            .with_ymd_and_hms(2024, 6, 1, 23, 30, 0)
+            .unwrap(),
+        templates: &templates,
+    };
+    let night = Condition::Time {
+        after: NaiveTime::from_hms_opt(22, 0, 0),
+        before: NaiveTime::from_hms_opt(6, 0, 0),
+        weekdays: vec![Weekday::Sat, Weekday::Sun],
+    };
+    assert!(night.check(&snapshot));
+    assert!(
+        Condition::Template("{{ is_state('sun.sun', 'below_horizon') }}".to_owned())
+            .check(&snapshot)
+    );
+    assert!(Condition::Not(vec![Condition::Template("{{ 0 }}".to_owned())]).check(&snapshot));
+    assert!(!Condition::State {
+        entity_id: kitchen,
+        state: vec!["on".to_owned()]
+    }
+    .check(&snapshot));
 }
 
+#[test]
+fn test_protocol_state_machine() {
+    use hass_rs::protocol::{ConnectionState, Outcome, Protocol};
+    use hass_rs::types::Response;
+    use serde_json::{json, Map};
+
+    let mut protocol = Protocol::new();
+    assert_eq!(protocol.state(), ConnectionState::Connecting);
+    assert!(matches!(
+        protocol.command("get_states", Map::new()),
+        Err(HassError::NotAuthenticated)
+    ));
+
+    let outcomes = protocol
+        .receive(r#"{"type":"auth_required","ha_version":"2025.1.0"}"#)
+        .unwrap();
+    assert!(
+        matches!(&outcomes[..], [Outcome::AuthRequired { ha_version }] if ha_version == "2025.1.0")
+    );
+
+    let frame = protocol.auth("token").unwrap();
+    let frame: serde_json::Value = serde_json::from_str(&frame).unwrap();
+    assert_eq!(frame, json!({"type": "auth", "access_token": "token"}));
+    assert_eq!(protocol.state(), ConnectionState::Authenticating);
+    assert!(matches!(
+        protocol.subscribe("subscribe_events", Map::new()),
+        Err(HassError::NotAuthenticated)
+    ));
+
+    let outcomes = protocol
+        .receive(r#"{"type":"auth_ok","ha_version":"2025.1.0"}"#)
+        .unwrap();
+    assert!(matches!(
+        &outcomes[..],
+        [Outcome::Response {
+            id: None,
+            response: Response::AuthOk(_)
+        }]
+    ));
+    assert_eq!(protocol.state(), ConnectionState::Authenticated);
+
+    // the ids are never reused
+    let (states, _) = protocol.command("get_states", Map::new()).unwrap();
+    let (config, _) = protocol.command("get_config", Map::new()).unwrap();
+    let (events, frame) = protocol
+        .subscribe(
+            "subscribe_events",
+            json!({"event_type": "state_changed"})
+                .as_object()
+                .cloned()
+                .unwrap(),
+        )
+        .unwrap();
+    assert_eq!((states, config, events), (1, 2, 3));
+    assert!(frame.contains(r#""event_type":"state_changed""#));
+
+    // out of order results, coalesced in a single frame
+    let outcomes = protocol
+        .receive(
+            r#"[{"id":2,"type":"result","success":true,"result":{}},
+                {"id":3,"type":"result","success":true,"result":null},
+                {"id":3,"type":"event","event":{"event_type":"state_changed"}}]"#,
+        )
+        .unwrap();
+    assert!(matches!(
+        &outcomes[..],
+        [
+            Outcome::Response { id: Some(2), .. },
+            Outcome::Response { id: Some(3), .. },
+            Outcome::Event { subscription: 3, event },
+        ] if event["event_type"] == "state_changed"
+    ));
+    assert!(protocol.is_pending(states));
+    assert!(!protocol.is_pending(config));
+
+    // a second result for the same id matches nothing
+    let outcomes = protocol
+        .receive(r#"{"id":2,"type":"result","success":true,"result":{}}"#)
+        .unwrap();
+    assert!(matches!(&outcomes[..], [Outcome::Unmatched(_)]));
+
+    // the late result of a cancelled request is dropped
+    protocol.cancel(states);
+    let outcomes = protocol
+        .receive(r#"{"id":1,"type":"result","success":true,"result":[]}"#)
+        .unwrap();
+    assert!(outcomes.is_empty());
+
+    // a failed subscription is not routed
+    let (logbook, _) = protocol
+        .subscribe("logbook/event_stream", Map::new())
+        .unwrap();
+    protocol
+        .receive(r#"{"id":4,"type":"result","success":false,"error":{"code":"invalid_format","message":"bad"}}"#)
+        .unwrap();
+    assert!(!protocol.is_subscribed(logbook));
+
+    let (unsubscribe, frame) = protocol.unsubscribe(events).unwrap();
+    assert_eq!(unsubscribe, 5);
+    assert!(frame.contains(r#""subscription":3"#));
+    let outcomes = protocol
+        .receive(r#"{"id":3,"type":"event","event":{"event_type":"state_changed"}}"#)
+        .unwrap();
+    assert!(matches!(
+        &outcomes[..],
+        [Outcome::UnmatchedEvent {
+            subscription: 3,
+            ..
+        }]
+    ));
+
+    // an invalid message of a coalesced frame does not lose the others
+    let (template, _) = protocol.command("render_template", Map::new()).unwrap();
+    let (services, _) = protocol.command("get_services", Map::new()).unwrap();
+    let outcomes = protocol
+        .receive(&format!(
+            r#"[{{"id":{template},"type":"result","success":true,"result":"on"}},
+                {{"id":99,"type":"no_such_type"}},
+                {{"id":{services},"type":"result","success":true,"result":{{}}}}]"#
+        ))
+        .unwrap();
+    assert!(matches!(
+        &outcomes[..],
+        [
+            Outcome::Response { id: Some(first), .. },
+            Outcome::Invalid { message, .. },
+            Outcome::Response { id: Some(last), .. },
+        ] if *first == template && message["id"] == 99 && *last == services
+    ));
+    assert!(!protocol.is_pending(template) && !protocol.is_pending(services));
+    // a single invalid message is still an error
+    assert!(protocol
+        .receive(r#"{"id":99,"type":"no_such_type"}"#)
+        .is_err());
+
+    // the close fails the requests still pending
+    let (ping, _) = protocol.command("ping", Map::new()).unwrap();
+    let outcomes = protocol.close("restarting");
+    assert!(matches!(
+        &outcomes[..],
+        [
+            Outcome::Response { id: Some(5), response: Response::Close(_) },
+            Outcome::Response { id: Some(id), response: Response::Close(reason) },
+        ] if *id == ping && reason == "restarting"
+    ));
+    assert_eq!(protocol.state(), ConnectionState::Closed);
+    assert!(matches!(
+        protocol.command("ping", Map::new()),
+        Err(HassError::ConnectionClosed)
+    ));
+}
 
 #[test]
 fn test_deserialize_event() {
@@ -495,5 +2386,622 @@ fn test_deserialize_event() {
     println!("DESERIALIZATION RESULT: {:?}", res);
     let res = res.unwrap();
     assert!(matches!(res, hass_rs::types::Response::Event(_)));
+
+    // a custom event keeps decoding with an entity id which is not one
+    for entity_id in [r#""Legacy Lamp""#, r#"["light.a", "light.b"]"#] {
+        let event: hass_rs::HassEvent = serde_json::from_str(&format!(
+            r#"{{
+                "event_type": "my_event",
+                "data": {{"entity_id": {entity_id}, "payload": 1}},
+                "origin": "REMOTE",
+                "time_fired": "2024-02-15T11:13:02.291378+00:00",
+                "context": {{"id": "01HPRMZAWNXKVVPSP11QFJ53HB", "parent_id": null, "user_id": null}}
+            }}"#
+        ))
+        .unwrap();
+        assert_eq!(event.data.entity_id, None);
+        assert_eq!(event.data.extra["payload"], 1);
+    }
+}
+
+#[test]
+fn test_typed_entity_state() {
+    use hass_rs::types::{HvacMode, WindBearing};
+
+    let light: HassEntity = serde_json::from_str(
+        r#"{
+            "entity_id": "light.kitchen",
+            "state": "on",
+            "attributes": {
+                "brightness": 180,
+                "color_mode": "hs",
+                "hs_color": [30.0, 60.5],
+                "friendly_name": "Kitchen",
+                "custom_attribute": 42
+            },
+            "last_changed": "2024-02-15T11:13:02.291378+00:00",
+            "last_updated": "2024-02-15T11:13:02.291378+00:00",
+            "context": null
+        }"#,
+    )
+    .unwrap();
+
+    let typed = light.as_light().unwrap();
+    assert_eq!(typed.state, StateValue::Value(OnOff::On));
+    assert_eq!(typed.attributes.brightness, Some(180));
+    assert_eq!(typed.attributes.hs_color, Some((30.0, 60.5)));
+    assert_eq!(typed.attributes.extra["custom_attribute"], 42);
+    assert!(matches!(
+        light.as_climate(),
+        Err(HassError::UnexpectedDomain { .. })
+    ));
+
+    let sensor = HassEntity {
+        entity_id: "sensor.outside_temperature".parse().unwrap(),
+        state: "21.5".to_owned(),
+        attributes: serde_json::json!({"unit_of_measurement": "°C", "device_class": "temperature"}),
+        ..light.clone()
+    };
+    let typed = sensor.as_sensor().unwrap();
+    assert_eq!(typed.state.value(), Some(&21.5));
+    assert_eq!(typed.attributes.unit_of_measurement.as_deref(), Some("°C"));
+
+    let unavailable = HassEntity {
+        state: "unavailable".to_owned(),
+        ..sensor
+    };
+    assert!(unavailable.as_sensor().unwrap().state.is_unavailable());
+
+    let climate = HassEntity {
+        entity_id: "climate.hall".parse().unwrap(),
+        state: "heat".to_owned(),
+        attributes: serde_json::json!({"hvac_modes": ["off", "heat", "heat_cool"]}),
+        ..light.clone()
+    };
+    assert_eq!(
+        climate.as_climate().unwrap().attributes.hvac_modes,
+        Some(vec![HvacMode::Off, HvacMode::Heat, HvacMode::HeatCool])
+    );
+
+    // the wind bearing is a number or a compass point depending on the integration
+    for (bearing, expected) in [
+        (serde_json::json!(315.0), WindBearing::Degrees(315.0)),
+        (
+            serde_json::json!("NW"),
+            WindBearing::Compass("NW".to_owned()),
+        ),
+    ] {
+        let weather = HassEntity {
+            entity_id: "weather.home".parse().unwrap(),
+            state: "sunny".to_owned(),
+            attributes: serde_json::json!({"wind_bearing": bearing}),
+            ..light.clone()
+        };
+        let typed = weather.as_weather().unwrap();
+        assert_eq!(typed.attributes.wind_bearing, Some(expected));
+    }
+}
+
+#[test]
+fn test_entity_id_and_domain() {
+    use hass_rs::types::{EntityId, HassDomain};
+
+    let entity_id: EntityId = "media_player.living_room_tv".parse().unwrap();
+    assert_eq!(entity_id.domain(), HassDomain::MediaPlayer);
+    assert_eq!(entity_id.object_id(), "living_room_tv");
+    assert_eq!(entity_id, "media_player.living_room_tv");
+
+    for invalid in [
+        "light",
+        "light.",
+        ".kitchen",
+        "Light.kitchen",
+        "light._x",
+        "light.a__b",
+    ] {
+        assert!(matches!(
+            EntityId::new(invalid),
+            Err(HassError::InvalidEntityId(_))
+        ));
+    }
+    assert!(serde_json::from_str::<EntityId>(r#""light""#).is_err());
+
+    let domain: HassDomain = serde_json::from_str(r#""my_custom""#).unwrap();
+    assert_eq!(domain, HassDomain::Other("my_custom".to_owned()));
+    assert_eq!(
+        serde_json::to_string(&HassDomain::InputNumber).unwrap(),
+        r#""input_number""#
+    );
+    assert_eq!(HassDomain::from("water_heater"), HassDomain::WaterHeater);
+    assert_eq!(HassDomain::HomeAssistant.to_string(), "homeassistant");
+}
+
+#[test]
+fn test_service_schema() {
+    let services: HassServices = serde_json::from_str(
+        r#"{
+            "light": {
+                "turn_on": {
+                    "name": "Turn on",
+                    "fields": {
+                        "transition": {
+                            "filter": {"supported_features": [32]},
+                            "selector": {"number": {"min": 0, "max": 300, "unit_of_measurement": "seconds"}}
+                        },
+                        "advanced_fields": {
+                            "collapsed": true,
+                            "fields": {
+                                "flash": {
+                                    "advanced": true,
+                                    "selector": {"select": {"options": [{"label": "Long", "value": "long"}, "short"]}}
+                                },
+                                "brightness_pct": {
+                                    "filter": {"attribute": {"supported_color_modes": ["brightness", "hs"]}},
+                                    "selector": {"number": {"min": 0, "max": 100}}
+                                }
+                            }
+                        }
+                    },
+                    "target": {"entity": [{"domain": ["light"]}]}
+                }
+            },
+            "weather": {
+                "get_forecasts": {
+                    "fields": {
+                        "type": {"required": true, "selector": {"select": {"options": ["daily", "hourly"]}}},
+                        "entity": {"selector": {"entity": {"domain": "weather", "multiple": true}}},
+                        "media": {"default": {}, "selector": {"media": null}}
+                    },
+                    "target": {"entity": {"domain": "weather", "supported_features": [1, 2]}},
+                    "response": {"optional": false}
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let turn_on = &services.0["light"]["turn_on"];
+    assert_eq!(turn_on.fields.len(), 1);
+    assert!(turn_on.sections["advanced_fields"].collapsed);
+    assert_eq!(turn_on.all_fields().count(), 3);
+    assert_eq!(
+        turn_on.fields["transition"]
+            .filter
+            .as_ref()
+            .unwrap()
+            .supported_features,
+        vec![32]
+    );
+    assert_eq!(
+        turn_on.target.as_ref().unwrap().entity[0].domain,
+        vec!["light"]
+    );
+    assert!(turn_on.response.is_none());
+
+    let flash = &turn_on.sections["advanced_fields"].fields["flash"];
+    assert!(flash.advanced);
+    match flash.selector.as_ref().unwrap() {
+        Selector::Select(select) => {
+            assert!(select.accepts("long"));
+            assert!(select.accepts("short"));
+            assert!(!select.accepts("medium"));
+        }
+        selector => panic!("unexpected selector {selector:?}"),
+    }
+
+    let get_forecasts = &services.0["weather"]["get_forecasts"];
+    assert!(get_forecasts.requires_response());
+    assert!(get_forecasts.fields["type"].required);
+    match get_forecasts.fields["entity"].selector.as_ref().unwrap() {
+        Selector::Entity(entity) => {
+            assert!(entity.multiple);
+            assert_eq!(entity.domains(), vec!["weather"]);
+        }
+        selector => panic!("unexpected selector {selector:?}"),
+    }
+    let media = get_forecasts.fields["media"].selector.as_ref().unwrap();
+    assert_eq!(media.kind(), "media");
+    assert_eq!(
+        get_forecasts.fields["media"].default,
+        Some(serde_json::json!({}))
+    );
+
+    // the sections are written back next to the fields
+    let value = serde_json::to_value(&services).unwrap();
+    assert_eq!(
+        value["light"]["turn_on"]["fields"]["advanced_fields"]["collapsed"],
+        true
+    );
+    assert_eq!(
+        serde_json::from_value::<HassServices>(value).unwrap(),
+        services
+    );
+}
+
+#[test]
+fn test_service_validation() {
+    use hass_rs::validation::{ServiceValidator, ValidationErrorKind};
+    use serde_json::json;
+
+    let services: HassServices = serde_json::from_value(json!({
+        "light": {
+            "turn_on": {
+                "fields": {
+                    "brightness_pct": {"selector": {"number": {"min": 0, "max": 100}}},
+                    "advanced_fields": {
+                        "collapsed": true,
+                        "fields": {"flash": {"selector": {"select": {"options": ["long", "short"]}}}}
+                    }
+                },
+                "target": {"entity": [{"domain": ["light"]}]}
+            }
+        },
+        "hue": {
+            "activate": {
+                "fields": {
+                    "entity": {"selector": {"entity": {
+                        "filter": [{"domain": "light"}, {"integration": "hue"}]
+                    }}},
+                    "sensor": {"selector": {"entity": {
+                        "domain": "sensor", "include_entities": ["weather.home"], "multiple": true
+                    }}},
+                    "any": {"selector": {"entity": {}}}
+                },
+                "target": {"entity": [{"domain": "light"}, {"integration": "hue"}]}
+            }
+        },
+        "scene": {
+            "apply": {
+                "fields": {
+                    "entities": {"required": true, "selector": {"object": {}}},
+                    "transition": {"selector": {"number": {"min": 0, "max": 300}}}
+                }
+            }
+        }
+    }))
+    .unwrap();
+    let validator = ServiceValidator::new(services);
+
+    let data = json!({"brightness_pct": "40", "flash": "short", "entity_id": ["light.a"]});
+    validator
+        .validate("light", "turn_on", Some(&data), None)
+        .unwrap();
+    validator
+        .validate_call(&light::TurnOn::new(Target::entity("light.kitchen")))
+        .unwrap();
+
+    let data = json!({
+        "brightness_pct": 120,
+        "flash": "medium",
+        "color": "red",
+        "entity_id": ["light.a", "switch.b"]
+    });
+    let target = Target::entity("sensor.c");
+    let Err(HassError::InvalidServiceCall { errors, .. }) =
+        validator.validate("light", "turn_on", Some(&data), Some(&target))
+    else {
+        panic!("the call should be invalid");
+    };
+    let mut paths: Vec<_> = errors.iter().map(|error| error.path.as_str()).collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![
+            "brightness_pct",
+            "color",
+            "entity_id[1]",
+            "flash",
+            "target.entity_id[0]"
+        ]
+    );
+    let brightness = errors.iter().find(|e| e.path == "brightness_pct").unwrap();
+    assert_eq!(
+        brightness.kind,
+        ValidationErrorKind::OutOfRange {
+            value: 120.0,
+            min: Some(0.0),
+            max: Some(100.0)
+        }
+    );
+    assert!(errors.iter().any(|e| e.kind
+        == ValidationErrorKind::UnexpectedDomain {
+            entity_id: "switch.b".to_owned(),
+            domains: vec!["light".to_owned()]
+        }));
+
+    let Err(HassError::InvalidServiceCall { errors, .. }) =
+        validator.validate("scene", "apply", None, Some(&target))
+    else {
+        panic!("the call should be invalid");
+    };
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].path, "entities");
+    assert_eq!(errors[0].kind, ValidationErrorKind::MissingField);
+    assert_eq!(errors[1].path, "target");
+
+    // a filter without domain accepts any domain, the legacy filter only applies when set
+    let data = json!({
+        "entity": "switch.hue_plug",
+        "sensor": ["sensor.lux", "weather.home"],
+        "any": "switch.kettle"
+    });
+    validator
+        .validate(
+            "hue",
+            "activate",
+            Some(&data),
+            Some(&Target::entity("switch.hue_plug")),
+        )
+        .unwrap();
+    let data = json!({"sensor": "switch.kettle"});
+    let Err(HassError::InvalidServiceCall { errors, .. }) =
+        validator.validate("hue", "activate", Some(&data), None)
+    else {
+        panic!("the call should be invalid");
+    };
+    assert_eq!(
+        errors[0].kind,
+        ValidationErrorKind::UnexpectedDomain {
+            entity_id: "switch.kettle".to_owned(),
+            domains: vec!["sensor".to_owned()]
+        }
+    );
+    // a list is only accepted by the selectors of multiple entities
+    let data = json!({"entity": ["light.a", "light.b"]});
+    let Err(HassError::InvalidServiceCall { errors, .. }) =
+        validator.validate("hue", "activate", Some(&data), None)
+    else {
+        panic!("the call should be invalid");
+    };
+    assert_eq!(errors[0].path, "entity");
+    assert!(matches!(
+        errors[0].kind,
+        ValidationErrorKind::InvalidType { .. }
+    ));
+
+    assert!(validator.validate("light", "explode", None, None).is_err());
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_chrono_timestamps() {
+    use chrono::{TimeZone, Utc};
+
+    let entity: HassEntity = serde_json::from_value(serde_json::json!({
+        "entity_id": "sensor.temperature",
+        "state": "21.5",
+        "attributes": {},
+        "last_changed": "2024-03-01T10:20:30.500000+01:00",
+        "last_updated": "not a timestamp",
+        "context": null
+    }))
+    .unwrap();
+    let last_changed = entity.last_changed_time().unwrap();
+    assert_eq!(
+        last_changed,
+        Utc.with_ymd_and_hms(2024, 3, 1, 9, 20, 30).unwrap() + chrono::Duration::milliseconds(500)
+    );
+    assert_eq!(entity.last_updated_time(), None);
+
+    let area: hass_rs::HassRegistryArea = serde_json::from_value(serde_json::json!({
+        "aliases": [], "area_id": "kitchen", "floor_id": null, "humidity_entity_id": null,
+        "icon": null, "labels": [], "name": "Kitchen", "picture": null,
+        "temperature_entity_id": null, "created_at": 1709284830.5, "modified_at": 0.0
+    }))
+    .unwrap();
+    assert_eq!(area.created_at_time(), Some(last_changed));
+    assert_eq!(
+        area.modified_at_time(),
+        Some(Utc.timestamp_opt(0, 0).unwrap())
+    );
+
+    let statistic: hass_rs::types::StatisticValue = serde_json::from_value(serde_json::json!({
+        "start": 1707994800000.0, "end": 1707995100000.0, "last_reset": null, "sum": 12.5
+    }))
+    .unwrap();
+    assert_eq!(
+        statistic.start_time(),
+        Some(Utc.with_ymd_and_hms(2024, 2, 15, 11, 0, 0).unwrap())
+    );
+    assert_eq!(
+        statistic.end_time(),
+        Some(Utc.with_ymd_and_hms(2024, 2, 15, 11, 5, 0).unwrap())
+    );
+    assert_eq!(statistic.last_reset_time(), None);
+
+    let entry: hass_rs::types::LogbookEntry =
+        serde_json::from_value(serde_json::json!({"when": 1709284830.5})).unwrap();
+    assert_eq!(entry.when_time(), area.created_at_time());
+}
+
+// the bindings generated from tests/fixtures/codegen_services.json, compiled with the tests
+#[cfg(feature = "codegen")]
+#[allow(dead_code)]
+mod codegen_services {
+    include!("fixtures/codegen_services.rs");
+}
+
+#[cfg(feature = "codegen")]
+#[test]
+fn test_codegen_services() {
+    use hass_rs::services::ServiceCall;
+
+    let services: hass_rs::HassServices =
+        serde_json::from_str(include_str!("fixtures/codegen_services.json")).unwrap();
+
+    let code = hass_rs::codegen::generate(&services);
+    assert_eq!(code, include_str!("fixtures/codegen_services.rs"));
+    assert!(code.contains("    /// Does the thing.\n"));
+    assert!(code.contains("        /// The level\n        pub level: f64,"));
+
+    // the names alike get a suffix, the field named target is still sent as `target`
+    let aim = codegen_services::my_custom_::Aim {
+        a_b: Some(true),
+        target_: "moon".to_owned(),
+        ..Default::default()
+    };
+    assert_eq!(
+        aim.service_data().unwrap(),
+        Some(serde_json::json!({"a-b": true, "target": "moon"}))
+    );
+    assert_eq!(
+        codegen_services::my_custom_::Svc22::default().service(),
+        "svc2"
+    );
+    assert_eq!(
+        codegen_services::my_custom::Ping::default().domain(),
+        "my-custom"
+    );
+    let thing = codegen_services::my_custom_::DoThing {
+        level: 3.0,
+        r#type: Some("a".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(
+        thing.service_data().unwrap(),
+        Some(serde_json::json!({"level": 3.0, "type": "a"}))
+    );
 }
 
+#[cfg(feature = "scheduler")]
+#[test]
+fn test_scheduler() {
+    use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+    use hass_rs::scheduler::{Cron, Location, Schedule, SunEvent};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+    fn assert_close(time: Option<DateTime<Utc>>, expected: DateTime<Utc>) {
+        let time = time.unwrap();
+        assert!(
+            (time - expected).abs() < TimeDelta::minutes(2),
+            "{time} is not {expected}"
+        );
+    }
+
+    let config: hass_rs::HassConfig = serde_json::from_value(serde_json::json!({
+        "latitude": 52.37, "longitude": 4.89, "elevation": 0, "location_name": "Home",
+        "time_zone": "Europe/Amsterdam", "version": "2024.6.0"
+    }))
+    .unwrap();
+    let amsterdam = Location::from_config(&config).unwrap();
+
+    // the times published for Amsterdam the 21st of June 2024, in UTC
+    let day = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+    assert_close(
+        amsterdam.sun_event(day, SunEvent::Dawn),
+        utc(2024, 6, 21, 2, 28),
+    );
+    assert_close(
+        amsterdam.sun_event(day, SunEvent::Sunrise),
+        utc(2024, 6, 21, 3, 18),
+    );
+    assert_close(
+        amsterdam.sun_event(day, SunEvent::Noon),
+        utc(2024, 6, 21, 11, 42),
+    );
+    assert_close(
+        amsterdam.sun_event(day, SunEvent::Sunset),
+        utc(2024, 6, 21, 20, 6),
+    );
+    assert_close(
+        amsterdam.sun_event(day, SunEvent::Dusk),
+        utc(2024, 6, 21, 20, 57),
+    );
+    let elevation = amsterdam.solar_elevation(utc(2024, 6, 21, 11, 42));
+    assert!((elevation - 61.07).abs() < 0.1, "{elevation}");
+    assert!(amsterdam.solar_elevation(utc(2024, 6, 21, 23, 42)) < -10.0);
+
+    let tromso = Location {
+        latitude: 69.65,
+        longitude: 18.96,
+        elevation: 0,
+        tz: "Europe/Oslo".parse().unwrap(),
+    };
+    assert_eq!(tromso.sun_event(day, SunEvent::Sunset), None);
+    let midnight_sun = tromso.next_sun_event(SunEvent::Sunset, utc(2024, 6, 21, 0, 0));
+    assert!(midnight_sun.unwrap() > utc(2024, 7, 20, 0, 0));
+
+    let before_sunset = Schedule::Sun {
+        event: SunEvent::Sunset,
+        offset: TimeDelta::minutes(-30),
+    };
+    assert_close(
+        before_sunset.next_after(&amsterdam, utc(2024, 6, 21, 12, 0)),
+        utc(2024, 6, 21, 19, 36),
+    );
+    // within the offset of today's sunset, the next one is tomorrow's
+    assert_close(
+        before_sunset.next_after(&amsterdam, utc(2024, 6, 21, 19, 50)),
+        utc(2024, 6, 22, 19, 36),
+    );
+
+    let workdays = Schedule::Cron("30 7 * * mon-fri".parse().unwrap());
+    assert_eq!(
+        workdays.next_after(&amsterdam, utc(2024, 3, 29, 8, 0)),
+        Some(utc(2024, 4, 1, 5, 30))
+    );
+    let quarters = Cron::new("*/15 9-17 * * *").unwrap();
+    assert_eq!(
+        quarters.next_after(amsterdam.tz, utc(2024, 6, 21, 7, 50)),
+        Some(utc(2024, 6, 21, 8, 0))
+    );
+    assert_eq!(quarters.to_string(), "*/15 9-17 * * *");
+    assert_eq!(
+        Cron::new("0 0 30 2 *")
+            .unwrap()
+            .next_after(amsterdam.tz, utc(2024, 1, 1, 0, 0)),
+        None
+    );
+    assert!(matches!(
+        Cron::new("61 * * * *"),
+        Err(HassError::InvalidCron(_))
+    ));
+    assert!(Cron::new("* * *").is_err());
+
+    // a step does not restrict the days of the month, only the Mondays of odd days match
+    let odd_mondays = Cron::new("0 12 */2 * mon").unwrap();
+    assert_eq!(
+        odd_mondays.next_after(amsterdam.tz, utc(2024, 6, 1, 0, 0)),
+        Some(utc(2024, 6, 3, 10, 0))
+    );
+    assert_eq!(
+        odd_mondays.next_after(amsterdam.tz, utc(2024, 6, 3, 10, 0)),
+        Some(utc(2024, 6, 17, 10, 0))
+    );
+
+    // 02:30 does not exist the day the clocks go forward, it fires when they went forward, and
+    // happens twice the day they go back
+    let night = Schedule::Cron(Cron::new("30 2 * * *").unwrap());
+    assert_eq!(
+        night.next_after(&amsterdam, utc(2024, 3, 30, 12, 0)),
+        Some(utc(2024, 3, 31, 1, 0))
+    );
+    assert_eq!(
+        night.next_after(&amsterdam, utc(2024, 3, 31, 1, 0)),
+        Some(utc(2024, 4, 1, 0, 30))
+    );
+    let quarters_at_two = Cron::new("*/15 2 * * *").unwrap();
+    assert_eq!(
+        quarters_at_two.next_after(amsterdam.tz, utc(2024, 3, 31, 1, 0)),
+        Some(utc(2024, 4, 1, 0, 0))
+    );
+    assert_eq!(
+        night.next_after(&amsterdam, utc(2024, 10, 26, 12, 0)),
+        Some(utc(2024, 10, 27, 0, 30))
+    );
+    assert_eq!(
+        night.next_after(&amsterdam, utc(2024, 10, 27, 0, 30)),
+        Some(utc(2024, 10, 28, 1, 30))
+    );
+    let daily = Schedule::Daily(NaiveTime::from_hms_opt(2, 30, 0).unwrap());
+    assert_eq!(
+        daily.next_after(&amsterdam, utc(2024, 3, 30, 12, 0)),
+        Some(utc(2024, 3, 31, 1, 0))
+    );
+    assert_eq!(
+        daily.next_after(&amsterdam, utc(2024, 10, 27, 0, 30)),
+        Some(utc(2024, 10, 28, 1, 30))
+    );
+}